@@ -1,3 +1,5 @@
+/* the standard 64x32 display */
 pub const WIDTH: usize = 64;
 pub const HEIGHT: usize = 32;
+/* 4kB of memory, as on the COSMAC VIP */
 pub const RAM_SIZE: usize = 4096;
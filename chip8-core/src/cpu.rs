@@ -0,0 +1,919 @@
+use std::fs;
+use std::io;
+use std::ops::Range;
+
+use crate::error::CpuError;
+use crate::font::BIG_FONT_ADDR;
+use crate::hash::rom_hash;
+use crate::instruction::Instruction;
+use crate::keypad::Keypad;
+use crate::platform::Machine;
+use crate::platform::Platform;
+use crate::quirks::Quirks;
+use crate::rng::Rng;
+
+use crate::consts::HEIGHT;
+use crate::consts::WIDTH;
+
+mod state;
+#[cfg(test)]
+mod tests;
+
+pub struct Cpu {
+    opcode: u16,
+    ram: Vec<u8>,
+    /* row major, width * height pixels. each pixel holds one bit per
+     * bitplane, so 0-3 on XO-CHIP and 0-1 on everything else */
+    vram: Vec<u8>,
+    vram_changed: bool,
+    /* bitplanes CLS, scrolling and DXYN draw to, set by FN01 */
+    planes: u8,
+    width: usize,
+    height: usize,
+    hires: bool,
+    v: [u8; 16],
+    i: usize,
+    pc: usize,
+    sp: usize,
+    stack: Vec<u16>,
+    delay_timer: u8,
+    sound_timer: u8,
+    /* XO-CHIP audio, loaded by F002 and FX3A */
+    pattern: Option<[u8; 16]>,
+    pitch: u8,
+    /* the HP-48 RPL user flags FX75/FX85 save registers to */
+    flags: [u8; 16],
+    /* set by 00FD */
+    halted: bool,
+    /* rom_hash() of the loaded ROM */
+    rom_hash: u64,
+    /* for CXNN. seeded the same every time unless set_rng() is called */
+    rng: Rng,
+    platform: Platform,
+    quirks: Quirks,
+    instructions_per_frame: u32,
+    /* set by DXYN under the display wait quirk, cleared by vblank() */
+    vblank_wait: bool,
+    /* the key FX0A saw go down. it finishes once the key comes back up */
+    key_wait: Option<usize>,
+    pub keypad: Keypad
+}
+
+/* what a frontend needs to know after each cycle */
+pub struct OutputState<'a> {
+    /* row major, width * height pixels. the value is the bitplanes the
+     * pixel is set in, 0-3 */
+    pub vram: &'a [u8],
+    pub width: usize,
+    pub height: usize,
+    pub vram_changed: bool,
+    pub beep: bool,
+    /* the XO-CHIP audio pattern to play while beep is set, once the
+     * program has loaded one, and its pitch */
+    pub pattern: Option<&'a [u8; 16]>,
+    pub pitch: u8,
+    /* the program ran 00FD and wants to exit */
+    pub halted: bool,
+}
+
+
+impl Default for Cpu {
+    fn default() -> Self {
+        Self::new(Platform::default())
+    }
+}
+
+impl Cpu {
+    pub fn new(platform: Platform) -> Self {
+        let mut ram = vec![0u8; platform.ram_size];
+
+        ram[..platform.font.len()].copy_from_slice(platform.font);
+        ram[BIG_FONT_ADDR..BIG_FONT_ADDR + platform.big_font.len()]
+            .copy_from_slice(platform.big_font);
+
+        Cpu {
+            opcode: 0,
+            ram,
+            vram: vec![0; WIDTH * HEIGHT],
+            vram_changed: false,
+            width: WIDTH,
+            height: HEIGHT,
+            hires: false,
+            planes: 0x01,
+            v: [0; 16],
+            i: 0x200,
+            pc: 0x200,
+            sp: 0,
+            stack: vec![0; platform.stack_depth],
+            delay_timer: 0,
+            sound_timer: 0,
+            pattern: None,
+            pitch: 64,
+            flags: [0; 16],
+            halted: false,
+            rom_hash: rom_hash(&[]),
+            rng: Rng::new(Default::default(), 0),
+            platform,
+            quirks: platform.quirks,
+            instructions_per_frame: platform.instructions_per_frame,
+            vblank_wait: false,
+            key_wait: None,
+            keypad: Keypad::new()
+        }
+    }
+
+    /* load the ROM file into memory */
+    pub fn load_rom(&mut self, filename: &str) -> io::Result<()> {
+        let rom_file = fs::read(filename)?;
+        self.load_rom_bytes(&rom_file);
+        Ok(())
+    }
+
+    /* load a ROM already in memory. anything past the end of RAM is
+     * dropped */
+    pub fn load_rom_bytes(&mut self, rom_file: &[u8]) {
+        self.rom_hash = rom_hash(rom_file);
+
+        for (i, &byte) in rom_file.iter().enumerate() {
+            let addr = 0x200 + i;
+
+            if addr < self.ram.len() {
+                self.ram[0x200 + i] = byte;
+            } else {
+                break;
+            }
+        }
+    }
+
+    pub fn rom_hash(&self) -> u64 {
+        self.rom_hash
+    }
+
+    pub fn platform(&self) -> &Platform {
+        &self.platform
+    }
+
+    pub fn quirks(&self) -> Quirks {
+        self.quirks
+    }
+
+    /* override the platform's quirks */
+    pub fn set_quirks(&mut self, quirks: Quirks) {
+        self.quirks = quirks;
+    }
+
+    pub fn rng(&self) -> &Rng {
+        &self.rng
+    }
+
+    /* reseed, or switch algorithm, for CXNN */
+    pub fn set_rng(&mut self, rng: Rng) {
+        self.rng = rng;
+    }
+
+    pub fn instructions_per_frame(&self) -> u32 {
+        self.instructions_per_frame
+    }
+
+    /* override the platform's clock speed */
+    pub fn set_instructions_per_frame(&mut self, instructions: u32) {
+        self.instructions_per_frame = instructions;
+    }
+
+    /* run one 60Hz frame: execute a frame's worth of instructions, or fewer
+     * if the program waits for vblank or exits, then tick the timers once.
+     * an error ends the frame early without the timer tick, output() then
+     * has what the frame drew up to that point */
+    pub fn run_frame(&mut self) -> Result<OutputState<'_>, CpuError> {
+        self.run_frame_with(|_, err| Err(err))
+    }
+
+    /* run_frame(), handing errors to on_error. it can deal with the error,
+     * say by skipping the instruction, and return Ok to carry on with the
+     * rest of the frame, or return an error to end the frame there */
+    pub fn run_frame_with(&mut self, mut on_error: impl FnMut(&mut Cpu, CpuError) -> Result<(), CpuError>)
+                          -> Result<OutputState<'_>, CpuError> {
+        let mut vram_changed = false;
+
+        for _ in 0..self.instructions_per_frame {
+            if self.vblank_wait || self.halted {
+                break;
+            }
+
+            let result = self.emulate_cycle().map(|_| ());
+            vram_changed |= self.vram_changed;
+
+            if let Err(err) = result.or_else(|err| on_error(self, err)) {
+                self.vram_changed = vram_changed;
+                return Err(err);
+            }
+        }
+
+        self.vblank();
+        self.vram_changed = vram_changed;
+
+        Ok(self.output())
+    }
+
+    /* emulate a single instruction. the timers don't tick, that is up to
+     * vblank(). run_frame() does both. on error the instruction hasn't run
+     * and pc still points at it */
+    pub fn emulate_cycle(&mut self) -> Result<OutputState<'_>, CpuError> {
+        self.vram_changed = false;
+
+        /* a sprite was drawn this frame and the display wait quirk is on,
+         * so nothing runs until the next vblank */
+        if !self.vblank_wait && !self.halted {
+            self.fetch_opcode()?;
+            self.opcode_execute()?;
+        }
+
+        Ok(self.output())
+    }
+
+    /* step over the instruction at pc without running it, e.g. to carry on
+     * past an error */
+    pub fn skip_instruction(&mut self) {
+        self.pc += self.instruction_size(self.pc);
+    }
+
+    /* the 60Hz vertical blank: the timers count down and a program waiting
+     * for the display can carry on */
+    pub fn vblank(&mut self) {
+        self.vblank_wait = false;
+
+        if self.delay_timer > 0 {
+            self.delay_timer -= 1;
+        }
+
+        if self.sound_timer > 0 {
+            self.sound_timer -= 1;
+        }
+    }
+
+    /* the display and sound as they are now */
+    pub fn output(&self) -> OutputState<'_> {
+        OutputState {
+            vram: &self.vram,
+            width: self.width,
+            height: self.height,
+            vram_changed: self.vram_changed,
+            beep: self.sound_timer > 0,
+            pattern: self.pattern.as_ref(),
+            pitch: self.pitch,
+            halted: self.halted,
+        }
+    }
+
+    pub fn pc(&self) -> usize {
+        self.pc
+    }
+
+    pub fn i(&self) -> usize {
+        self.i
+    }
+
+    /* V0-VF */
+    pub fn registers(&self) -> &[u8; 16] {
+        &self.v
+    }
+
+    /* the return addresses in use, oldest first */
+    pub fn stack(&self) -> &[u16] {
+        &self.stack[..self.sp]
+    }
+
+    pub fn delay_timer(&self) -> u8 {
+        self.delay_timer
+    }
+
+    pub fn sound_timer(&self) -> u8 {
+        self.sound_timer
+    }
+
+    pub fn memory(&self) -> &[u8] {
+        &self.ram
+    }
+
+    /* fetch opcode from RAM */
+    fn fetch_opcode(&mut self) -> Result<(), CpuError> {
+        self.opcode = self.read_word(self.pc)?;
+        Ok(())
+    }
+
+    /* big-endian 16-bit word from RAM */
+    fn read_word(&self, addr: usize) -> Result<u16, CpuError> {
+        let range = self.ram_range(addr, 2)?;
+        Ok((self.ram[range.start] as u16) << 8 | (self.ram[range.start + 1] as u16))
+    }
+
+    /* the len bytes of RAM starting at addr, or an error if they run past
+     * the end of it */
+    fn ram_range(&self, addr: usize, len: usize) -> Result<Range<usize>, CpuError> {
+        if addr + len <= self.ram.len() {
+            Ok(addr..addr + len)
+        } else {
+            Err(CpuError::MemoryOutOfBounds { pc: self.pc, addr: addr.max(self.ram.len()) })
+        }
+    }
+
+    /* the size of the instruction at addr. anything that can't run counts
+     * as 2 bytes */
+    fn instruction_size(&self, addr: usize) -> usize {
+        match self.read_word(addr).ok().and_then(Instruction::decode) {
+            Some(instr) if self.platform.supports(&instr) => instr.size(),
+            _ => 2,
+        }
+    }
+
+    /* execute the opcode */
+    fn opcode_execute(&mut self) -> Result<(), CpuError> {
+        let instr = match Instruction::decode(self.opcode) {
+            Some(instr) if self.platform.supports(&instr) => instr,
+            _ => return Err(CpuError::InvalidOpcode { pc: self.pc, opcode: self.opcode }),
+        };
+
+        match instr {
+            Instruction::Nop => self.op_0000(),
+            Instruction::Cls => self.op_00e0(),
+            Instruction::Rts => self.op_00ee()?,
+            Instruction::ScrollDown { n } => self.op_00cn(n as usize),
+            Instruction::ScrollUp { n } => self.op_00dn(n as usize),
+            Instruction::ScrollRight => self.op_00fb(),
+            Instruction::ScrollLeft => self.op_00fc(),
+            Instruction::Exit => self.op_00fd(),
+            Instruction::Lores => self.op_00fe(),
+            Instruction::Hires => self.op_00ff(),
+            Instruction::Jmp { nnn } => self.op_1nnn(nnn as usize),
+            Instruction::Call { nnn } => self.op_2nnn(nnn as usize)?,
+            Instruction::SkipEqImm { x, nn } => self.op_3xnn(x as usize, nn),
+            Instruction::SkipNeImm { x, nn } => self.op_4xnn(x as usize, nn),
+            Instruction::SkipEq { x, y } => self.op_5xy0(x as usize, y as usize),
+            Instruction::StoreRange { x, y } => self.op_5xy2(x as usize, y as usize)?,
+            Instruction::LoadRange { x, y } => self.op_5xy3(x as usize, y as usize)?,
+            Instruction::Mvi { x, nn } => self.op_6xnn(x as usize, nn),
+            Instruction::Adi { x, nn } => self.op_7xnn(x as usize, nn),
+            Instruction::Mov { x, y } => self.op_8xy0(x as usize, y as usize),
+            Instruction::Or { x, y } => self.op_8xy1(x as usize, y as usize),
+            Instruction::And { x, y } => self.op_8xy2(x as usize, y as usize),
+            Instruction::Xor { x, y } => self.op_8xy3(x as usize, y as usize),
+            Instruction::Add { x, y } => self.op_8xy4(x as usize, y as usize),
+            Instruction::Sub { x, y } => self.op_8xy5(x as usize, y as usize),
+            Instruction::Shr { x, y } => self.op_8xy6(x as usize, y as usize),
+            Instruction::Subb { x, y } => self.op_8xy7(x as usize, y as usize),
+            Instruction::Shl { x, y } => self.op_8xye(x as usize, y as usize),
+            Instruction::SkipNe { x, y } => self.op_9xy0(x as usize, y as usize),
+            Instruction::MviI { nnn } => self.op_annn(nnn as usize),
+            Instruction::JmpV0 { nnn } => self.op_bnnn(nnn as usize),
+            Instruction::Rndmsk { x, nn } => self.op_cxnn(x as usize, nn),
+            Instruction::Sprite { x, y, n } => {
+                self.op_dxyn(x as usize, y as usize, n as usize)?
+            },
+            Instruction::SkipKeyY { x } => self.op_ex9e(x as usize),
+            Instruction::SkipKeyN { x } => self.op_exa1(x as usize),
+            Instruction::LongI => self.op_f000()?,
+            Instruction::Plane { x } => self.op_fn01(x as usize),
+            Instruction::Audio => self.op_f002()?,
+            Instruction::GetDelay { x } => self.op_fx07(x as usize),
+            Instruction::Key { x } => self.op_fx0a(x as usize),
+            Instruction::SetDelay { x } => self.op_fx15(x as usize),
+            Instruction::SetSound { x } => self.op_fx18(x as usize),
+            Instruction::AdiI { x } => self.op_fx1e(x as usize),
+            Instruction::SpriteChar { x } => self.op_fx29(x as usize),
+            Instruction::BigSpriteChar { x } => self.op_fx30(x as usize),
+            Instruction::MovBcd { x } => self.op_fx33(x as usize)?,
+            Instruction::Pitch { x } => self.op_fx3a(x as usize),
+            Instruction::Store { x } => self.op_fx55(x as usize)?,
+            Instruction::Load { x } => self.op_fx65(x as usize)?,
+            Instruction::StoreFlags { x } => self.op_fx75(x as usize),
+            Instruction::LoadFlags { x } => self.op_fx85(x as usize),
+        };
+
+        Ok(())
+    }
+
+    /* NOP */
+    fn op_0000(&mut self) {
+        self.pc += 2;
+    }
+
+    /* CLS */
+    fn op_00e0(&mut self) {
+        for pixel in self.vram.iter_mut() {
+            *pixel &= !self.planes;
+        }
+
+        self.vram_changed = true;
+        self.pc += 2;
+    }
+
+    /* RTS */
+    fn op_00ee(&mut self) -> Result<(), CpuError> {
+        if self.sp == 0 {
+            return Err(CpuError::StackUnderflow { pc: self.pc });
+        }
+
+        self.sp -= 1;
+        self.pc = self.stack[self.sp] as usize;
+        Ok(())
+    }
+
+    /* SCROLL.D */
+    fn op_00cn(&mut self, n: usize) {
+        self.scroll(0, n as isize);
+        self.pc += 2;
+    }
+
+    /* SCROLL.U */
+    fn op_00dn(&mut self, n: usize) {
+        self.scroll(0, -(n as isize));
+        self.pc += 2;
+    }
+
+    /* SCROLL.R */
+    fn op_00fb(&mut self) {
+        self.scroll(4, 0);
+        self.pc += 2;
+    }
+
+    /* SCROLL.L */
+    fn op_00fc(&mut self) {
+        self.scroll(-4, 0);
+        self.pc += 2;
+    }
+
+    /* EXIT */
+    fn op_00fd(&mut self) {
+        self.halted = true;
+    }
+
+    /* LORES */
+    fn op_00fe(&mut self) {
+        self.set_hires(false);
+        self.pc += 2;
+    }
+
+    /* HIRES */
+    fn op_00ff(&mut self) {
+        self.set_hires(true);
+        self.pc += 2;
+    }
+
+    /* JMP */
+    fn op_1nnn(&mut self, nnn: usize) {
+        self.pc = nnn;
+    }
+
+    /* CALL */
+    fn op_2nnn(&mut self, nnn: usize) -> Result<(), CpuError> {
+        if self.sp == self.stack.len() {
+            return Err(CpuError::StackOverflow { pc: self.pc });
+        }
+
+        /* a CALL in the last word of XO-CHIP's 64kB returns to 0 */
+        self.stack[self.sp] = (self.pc + 2) as u16;
+        self.sp += 1;
+        self.pc = nnn;
+        Ok(())
+    }
+
+    /* SKIP.EQ */
+    fn op_3xnn(&mut self, x: usize, nn: u8) {
+        self.skip_if(self.v[x] == nn);
+    }
+
+    /* SKIP.NE */
+    fn op_4xnn(&mut self, x: usize, nn: u8) {
+        self.skip_if(self.v[x] != nn);
+    }
+
+    /* SKIP.EQ */
+    fn op_5xy0(&mut self, x: usize, y: usize) {
+        self.skip_if(self.v[x] == self.v[y]);
+    }
+
+    /* MOVM */
+    fn op_5xy2(&mut self, x: usize, y: usize) -> Result<(), CpuError> {
+        let regs = register_range(x, y);
+        let range = self.ram_range(self.i, regs.len())?;
+
+        for (addr, reg) in range.zip(regs) {
+            self.ram[addr] = self.v[reg];
+        }
+
+        self.pc += 2;
+        Ok(())
+    }
+
+    /* MOVM */
+    fn op_5xy3(&mut self, x: usize, y: usize) -> Result<(), CpuError> {
+        let regs = register_range(x, y);
+        let range = self.ram_range(self.i, regs.len())?;
+
+        for (addr, reg) in range.zip(regs) {
+            self.v[reg] = self.ram[addr];
+        }
+
+        self.pc += 2;
+        Ok(())
+    }
+
+    /* MVI */
+    fn op_6xnn(&mut self, x: usize, nn: u8) {
+        self.v[x] = nn;
+        self.pc += 2;
+    }
+
+    /* ADI */
+    fn op_7xnn(&mut self, x: usize, nn: u8) {
+        let vx = self.v[x] as u16;
+        let val = nn as u16;
+        let result = vx + val;
+        self.v[x] = result as u8;
+        self.pc += 2;
+    }
+
+    /* MOV. */
+    fn op_8xy0(&mut self, x: usize, y: usize) {
+        self.v[x] = self.v[y];
+        self.pc += 2;
+    }
+
+    /* OR. */
+    fn op_8xy1(&mut self, x: usize, y: usize) {
+        self.v[x] |= self.v[y];
+        self.logic_vf_reset();
+        self.pc += 2;
+    }
+
+    /* AND . */
+    fn op_8xy2(&mut self, x: usize, y: usize) {
+        self.v[x] &= self.v[y];
+        self.logic_vf_reset();
+        self.pc += 2;
+    }
+
+    /* XOR. */
+    fn op_8xy3(&mut self, x: usize, y: usize) {
+        self.v[x] ^= self.v[y];
+        self.logic_vf_reset();
+        self.pc += 2;
+    }
+
+    /* ADD. */
+    fn op_8xy4(&mut self, x: usize, y: usize) {
+        let vx = self.v[x] as u16;
+        let vy = self.v[y] as u16;
+        let result = vx + vy;
+        self.v[x] = result as u8;
+        self.v[0x0f] = if result > 0xFF { 1 } else { 0 };
+        self.pc += 2;
+    }
+
+    /* SUB. */
+    fn op_8xy5(&mut self, x: usize, y: usize) {
+        /* VF is 1 when there's no borrow. it's written last so the flag
+         * wins when VF is VX */
+        let (vx, vy) = (self.v[x], self.v[y]);
+        self.v[x] = vx.wrapping_sub(vy);
+        self.v[0x0f] = if vx >= vy { 1 } else { 0 };
+        self.pc += 2;
+    }
+
+    /* SHR. */
+    fn op_8xy6(&mut self, x: usize, y: usize) {
+        let val = self.shift_source(x, y);
+        self.v[x] = val >> 1;
+        self.v[0x0f] = val & 0x01;
+        self.pc += 2;
+    }
+
+    /* SUBB. */
+    fn op_8xy7(&mut self, x: usize, y: usize) {
+        let (vx, vy) = (self.v[x], self.v[y]);
+        self.v[x] = vy.wrapping_sub(vx);
+        self.v[0x0f] = if vy >= vx { 1 } else { 0 };
+        self.pc += 2;
+    }
+
+    /* SHL */
+    fn op_8xye(&mut self, x: usize, y: usize) {
+        let val = self.shift_source(x, y);
+        self.v[x] = val << 1;
+        self.v[0x0f] = (val & 0b10000000) >> 7;
+        self.pc += 2;
+    }
+
+    /* SKIP.NE */
+    fn op_9xy0(&mut self, x: usize, y: usize) {
+        self.skip_if(self.v[x] != self.v[y]);
+    }
+
+    /* MVI */
+    fn op_annn(&mut self, nnn: usize) {
+        self.i = nnn;
+        self.pc += 2;
+    }
+
+    /* JMP */
+    fn op_bnnn(&mut self, nnn: usize) {
+        let reg = if self.quirks.jump_with_vx { (nnn >> 8) & 0x0f } else { 0 };
+        self.pc = nnn + self.v[reg] as usize;
+    }
+
+    /* RNDMSK */
+    fn op_cxnn(&mut self, x: usize, nn: u8) {
+        self.v[x] = nn & self.rng.next_byte();
+        self.pc += 2;
+    }
+
+    /* SPRITE */
+    fn op_dxyn(&mut self, x: usize, y: usize, n: usize) -> Result<(), CpuError> {
+        /* DXY0 draws a 16x16 sprite, two bytes per row, in SUPER-CHIP's
+         * hires mode and everywhere on XO-CHIP. SUPER-CHIP's lores mode draws
+         * 8x16 instead, and the VIP just draws nothing */
+        let (rows, cols) = match self.platform.machine {
+            _ if n != 0 => (n, 8),
+            Machine::XoChip => (16, 16),
+            Machine::SuperChip if self.hires => (16, 16),
+            Machine::SuperChip => (16, 8),
+            Machine::Chip8 => (0, 8),
+        };
+
+        /* the starting position always wraps, the quirk only decides what
+         * happens to the part of the sprite that runs off the edge */
+        let (width, height) = (self.width, self.height);
+        let x0 = self.v[x] as usize % width;
+        let y0 = self.v[y] as usize % height;
+        let clip = self.quirks.clip_sprites;
+
+        /* in hires mode SUPER-CHIP sets VF to the number of sprite rows that
+         * collided or were clipped off the bottom, not just 0 or 1 */
+        let count_rows = self.platform.machine == Machine::SuperChip && self.hires;
+        let mut collisions = 0;
+
+        /* with both XO-CHIP planes selected the sprite data for the second
+         * plane follows straight after the first's */
+        let sprite_len = rows * cols / 8 * (self.planes & 0x03).count_ones() as usize;
+        self.ram_range(self.i, sprite_len)?;
+        let mut addr = self.i;
+
+        for plane in [0x01, 0x02] {
+            if self.planes & plane == 0 {
+                continue;
+            }
+
+            for row in 0..rows {
+                if clip && y0 + row >= height {
+                    if count_rows {
+                        collisions += rows - row;
+                    }
+                    break;
+                }
+                let y = (y0 + row) % height;
+                let mut collided = false;
+
+                for col in 0..cols {
+                    if clip && x0 + col >= width {
+                        break;
+                    }
+                    let x = (x0 + col) % width;
+                    let byte = self.ram[addr + row * cols / 8 + col / 8];
+
+                    if (byte >> (7 - col % 8)) & 0x01 == 1 {
+                        let pixel = &mut self.vram[y * width + x];
+                        collided |= *pixel & plane != 0;
+                        *pixel ^= plane;
+                    }
+                }
+
+                if collided {
+                    collisions += 1;
+                }
+            }
+
+            addr += rows * cols / 8;
+        }
+
+        self.v[0x0f] = if count_rows { collisions as u8 } else { (collisions > 0) as u8 };
+        self.vram_changed = true;
+        self.vblank_wait = self.quirks.display_wait;
+        self.pc += 2;
+        Ok(())
+    }
+
+    /* SKIPKEY.Y */
+    fn op_ex9e(&mut self, x: usize) {
+        self.skip_if(self.keypad.pressed(self.v[x] as usize & 0x0f));
+    }
+
+    /* SKIPKEY.N */
+    fn op_exa1(&mut self, x: usize) {
+        self.skip_if(!self.keypad.pressed(self.v[x] as usize & 0x0f));
+    }
+
+    /* MVI.L */
+    fn op_f000(&mut self) -> Result<(), CpuError> {
+        self.i = self.read_word(self.pc + 2)? as usize;
+        self.pc += 4;
+        Ok(())
+    }
+
+    /* PLANE */
+    fn op_fn01(&mut self, n: usize) {
+        self.planes = n as u8 & 0x03;
+        self.pc += 2;
+    }
+
+    /* AUDIO */
+    fn op_f002(&mut self) -> Result<(), CpuError> {
+        let mut pattern = [0; 16];
+        pattern.copy_from_slice(&self.ram[self.ram_range(self.i, 16)?]);
+        self.pattern = Some(pattern);
+        self.pc += 2;
+        Ok(())
+    }
+
+    /* MOV */
+    fn op_fx07(&mut self, x: usize) {
+        self.v[x] = self.delay_timer;
+        self.pc += 2;
+    }
+
+    /* KEY */
+    fn op_fx0a(&mut self, x: usize) {
+        self.wait_keypress(x);
+    }
+
+    /* MOV */
+    fn op_fx15(&mut self, x: usize) {
+        self.delay_timer = self.v[x];
+        self.pc += 2;
+    }
+
+    /* MOV */
+    fn op_fx18(&mut self, x: usize) {
+        self.sound_timer = self.v[x];
+        self.pc += 2;
+    }
+
+    /* ADI */
+    fn op_fx1e(&mut self, x: usize) {
+        self.i = (self.i + self.v[x] as usize) & 0xffff;
+
+        if self.quirks.index_overflow {
+            self.v[0x0f] = if self.i > 0x0f00 { 1 } else { 0 };
+        }
+        self.pc += 2;
+    }
+
+    /* SPRITECHAR */
+    fn op_fx29(&mut self, x: usize) {
+        self.i = (self.v[x] as usize) * 5;
+        self.pc += 2;
+    }
+
+    /* BIGSPRITECHAR */
+    fn op_fx30(&mut self, x: usize) {
+        self.i = BIG_FONT_ADDR + (self.v[x] as usize & 0x0f) * 10;
+        self.pc += 2;
+    }
+
+    /* MOVBCD */
+    fn op_fx33(&mut self, x: usize) -> Result<(), CpuError> {
+        let range = self.ram_range(self.i, 3)?;
+        let vx = self.v[x];
+        self.ram[range].copy_from_slice(&[vx / 100, (vx % 100) / 10, vx % 10]);
+        self.pc += 2;
+        Ok(())
+    }
+
+    /* MOV */
+    fn op_fx3a(&mut self, x: usize) {
+        self.pitch = self.v[x];
+        self.pc += 2;
+    }
+
+    /* MOVM */
+    fn op_fx55(&mut self, x: usize) -> Result<(), CpuError> {
+        let range = self.ram_range(self.i, x + 1)?;
+        self.ram[range].copy_from_slice(&self.v[..x + 1]);
+
+        if self.quirks.load_store_increments_i {
+            self.i += x + 1;
+        }
+
+        self.pc += 2;
+        Ok(())
+    }
+
+    /* MOVM */
+    fn op_fx65(&mut self, x: usize) -> Result<(), CpuError> {
+        let range = self.ram_range(self.i, x + 1)?;
+        self.v[..x + 1].copy_from_slice(&self.ram[range]);
+
+        if self.quirks.load_store_increments_i {
+            self.i += x + 1;
+        }
+
+        self.pc += 2;
+        Ok(())
+    }
+
+
+    /* MOVM */
+    fn op_fx75(&mut self, x: usize) {
+        self.flags[..x + 1].copy_from_slice(&self.v[..x + 1]);
+        self.pc += 2;
+    }
+
+    /* MOVM */
+    fn op_fx85(&mut self, x: usize) {
+        self.v[..x + 1].copy_from_slice(&self.flags[..x + 1]);
+        self.pc += 2;
+    }
+
+    /* move the selected planes dx pixels right and dy down. whatever
+     * scrolls in from the edge is blank */
+    fn scroll(&mut self, dx: isize, dy: isize) {
+        let (width, height) = (self.width as isize, self.height as isize);
+        let old = self.vram.clone();
+
+        for y in 0..height {
+            for x in 0..width {
+                let (sx, sy) = (x - dx, y - dy);
+                let src = if (0..width).contains(&sx) && (0..height).contains(&sy) {
+                    old[(sy * width + sx) as usize]
+                } else {
+                    0
+                };
+
+                let pixel = &mut self.vram[(y * width + x) as usize];
+                *pixel = (*pixel & !self.planes) | (src & self.planes);
+            }
+        }
+
+        self.vram_changed = true;
+    }
+
+    /* switch display mode, which also clears the screen */
+    fn set_hires(&mut self, hires: bool) {
+        let (width, height) = if hires {
+            (self.platform.width, self.platform.height)
+        } else {
+            (WIDTH, HEIGHT)
+        };
+
+        self.hires = hires;
+        self.width = width;
+        self.height = height;
+        self.vram = vec![0; width * height];
+        self.vram_changed = true;
+    }
+
+    /* skip the next instruction if cond holds. on XO-CHIP that instruction
+     * may be the 4 byte F000 NNNN, which has to be skipped whole */
+    fn skip_if(&mut self, cond: bool) {
+        self.pc += 2;
+
+        if cond {
+            self.pc += self.instruction_size(self.pc);
+        }
+    }
+
+    /* the value 8XY6/8XYE shift */
+    fn shift_source(&self, x: usize, y: usize) -> u8 {
+        if self.quirks.shift_uses_vy { self.v[y] } else { self.v[x] }
+    }
+
+    /* the COSMAC VIP's logic ops clobber VF as a side effect */
+    fn logic_vf_reset(&mut self) {
+        if self.quirks.vf_reset {
+            self.v[0x0f] = 0;
+        }
+    }
+
+    /* like the COSMAC VIP, wait for a key to be pressed and then released.
+     * pc stays put until then so FX0A runs again every cycle, and the timers
+     * carry on ticking in the meantime */
+    fn wait_keypress(&mut self, x: usize) {
+        match self.key_wait {
+            None => self.key_wait = self.keypad.first_pressed(),
+            Some(key) if !self.keypad.pressed(key) => {
+                self.v[x] = key as u8;
+                self.key_wait = None;
+                self.pc += 2;
+            },
+            Some(_) => {},
+        }
+    }
+}
+
+/* the registers 5XY2/5XY3 touch, VX to VY in either direction */
+fn register_range(x: usize, y: usize) -> Vec<usize> {
+    if x <= y {
+        (x..=y).collect()
+    } else {
+        (y..=x).rev().collect()
+    }
+}
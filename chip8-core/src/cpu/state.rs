@@ -0,0 +1,238 @@
+use crate::error::StateError;
+use crate::quirks::Quirks;
+use crate::rng::RandomAlgorithm;
+use crate::rng::Rng;
+
+use crate::consts::HEIGHT;
+use crate::consts::WIDTH;
+
+use super::Cpu;
+
+#[cfg(test)]
+mod tests;
+
+
+/* a save state is everything in the Cpu, little endian:
+ *
+ *   "CH8S" version:u16 rom_hash:u64
+ *   platform name:(len:u8 bytes) quirks:u8 instructions_per_frame:u32
+ *   rng algorithm:u8 state:u64
+ *   opcode:u16 v:16 i:u32 pc:u32 stack:(len:u8 u16...) sp:u8
+ *   delay:u8 sound:u8 flags:16
+ *   hires:u8 planes:u8 width:u16 height:u16 vram:(len:u32 bytes)
+ *   pattern:(present:u8 16) pitch:u8
+ *   halted:u8 vblank_wait:u8 key_wait:u8 (0xff if not waiting) keypad:u16
+ *   ram:(len:u32 bytes)
+ *
+ * bump VERSION whenever this changes */
+const MAGIC: &[u8; 4] = b"CH8S";
+const VERSION: u16 = 1;
+
+impl Cpu {
+    /* snapshot the whole machine */
+    pub fn save_state(&self) -> Vec<u8> {
+        let mut w = Writer(Vec::with_capacity(self.ram.len() + self.vram.len() + 128));
+
+        w.bytes(MAGIC);
+        w.u16(VERSION);
+        w.u64(self.rom_hash);
+
+        w.u8(self.platform.name.len() as u8);
+        w.bytes(self.platform.name.as_bytes());
+        w.u8(self.quirks.bits());
+        w.u32(self.instructions_per_frame);
+
+        w.u8(match self.rng.algorithm() {
+            RandomAlgorithm::SplitMix => 0,
+        });
+        w.u64(self.rng.state());
+
+        w.u16(self.opcode);
+        w.bytes(&self.v);
+        w.u32(self.i as u32);
+        w.u32(self.pc as u32);
+        w.u8(self.stack.len() as u8);
+        for &addr in &self.stack {
+            w.u16(addr);
+        }
+        w.u8(self.sp as u8);
+        w.u8(self.delay_timer);
+        w.u8(self.sound_timer);
+        w.bytes(&self.flags);
+
+        w.u8(self.hires as u8);
+        w.u8(self.planes);
+        w.u16(self.width as u16);
+        w.u16(self.height as u16);
+        w.u32(self.vram.len() as u32);
+        w.bytes(&self.vram);
+
+        match self.pattern {
+            Some(pattern) => {
+                w.u8(1);
+                w.bytes(&pattern);
+            },
+            None => {
+                w.u8(0);
+                w.bytes(&[0; 16]);
+            },
+        }
+        w.u8(self.pitch);
+
+        w.u8(self.halted as u8);
+        w.u8(self.vblank_wait as u8);
+        w.u8(self.key_wait.map_or(0xff, |key| key as u8));
+        w.u16(self.keypad.bits());
+
+        w.u32(self.ram.len() as u32);
+        w.bytes(&self.ram);
+
+        w.0
+    }
+
+    /* restore a snapshot taken by save_state(). it has to be of the ROM
+     * that's loaded. nothing changes if it can't be loaded */
+    pub fn load_state(&mut self, data: &[u8]) -> Result<(), StateError> {
+        let mut r = Reader { data, pos: 0 };
+
+        if r.bytes(MAGIC.len()).ok() != Some(&MAGIC[..]) {
+            return Err(StateError::NotAState);
+        }
+
+        let version = r.u16()?;
+        if version > VERSION {
+            return Err(StateError::UnsupportedVersion(version));
+        }
+
+        let saved = r.u64()?;
+        if saved != self.rom_hash {
+            return Err(StateError::WrongRom { loaded: self.rom_hash, saved });
+        }
+
+        let name_len = r.u8()? as usize;
+        let name = std::str::from_utf8(r.bytes(name_len)?).map_err(|_| StateError::Corrupt("platform"))?;
+        let platform = name.parse().map_err(|_| StateError::Corrupt("platform"))?;
+
+        let mut cpu = Cpu::new(platform);
+        cpu.rom_hash = saved;
+        cpu.quirks = Quirks::from_bits(r.u8()?);
+        cpu.instructions_per_frame = r.u32()?;
+
+        let algorithm = match r.u8()? {
+            0 => RandomAlgorithm::SplitMix,
+            _ => return Err(StateError::Corrupt("random algorithm")),
+        };
+        cpu.rng = Rng::new(algorithm, r.u64()?);
+
+        cpu.opcode = r.u16()?;
+        cpu.v.copy_from_slice(r.bytes(16)?);
+        cpu.i = r.u32()? as usize;
+        cpu.pc = r.u32()? as usize;
+        if r.u8()? as usize != cpu.stack.len() {
+            return Err(StateError::Corrupt("stack depth"));
+        }
+        for addr in cpu.stack.iter_mut() {
+            *addr = r.u16()?;
+        }
+        cpu.sp = r.u8()? as usize;
+        if cpu.sp > cpu.stack.len() {
+            return Err(StateError::Corrupt("stack pointer"));
+        }
+        cpu.delay_timer = r.u8()?;
+        cpu.sound_timer = r.u8()?;
+        cpu.flags.copy_from_slice(r.bytes(16)?);
+
+        cpu.hires = r.u8()? != 0;
+        cpu.planes = r.u8()? & 0x03;
+        cpu.width = r.u16()? as usize;
+        cpu.height = r.u16()? as usize;
+        let expected = if cpu.hires { (platform.width, platform.height) } else { (WIDTH, HEIGHT) };
+        if (cpu.width, cpu.height) != expected {
+            return Err(StateError::Corrupt("display size"));
+        }
+        let vram_len = r.u32()? as usize;
+        if vram_len != cpu.width * cpu.height {
+            return Err(StateError::Corrupt("display size"));
+        }
+        cpu.vram = r.bytes(vram_len)?.to_vec();
+
+        let has_pattern = r.u8()? != 0;
+        let mut pattern = [0; 16];
+        pattern.copy_from_slice(r.bytes(16)?);
+        cpu.pattern = if has_pattern { Some(pattern) } else { None };
+        cpu.pitch = r.u8()?;
+
+        cpu.halted = r.u8()? != 0;
+        cpu.vblank_wait = r.u8()? != 0;
+        cpu.key_wait = match r.u8()? {
+            0xff => None,
+            key => Some(key as usize & 0x0f),
+        };
+        cpu.keypad.set_bits(r.u16()?);
+
+        let ram_len = r.u32()? as usize;
+        if ram_len != cpu.ram.len() {
+            return Err(StateError::Corrupt("memory size"));
+        }
+        cpu.ram.copy_from_slice(r.bytes(ram_len)?);
+
+        /* the frontend has to redraw whatever was on screen before */
+        cpu.vram_changed = true;
+
+        *self = cpu;
+        Ok(())
+    }
+}
+
+struct Writer(Vec<u8>);
+
+impl Writer {
+    fn u8(&mut self, value: u8) {
+        self.0.push(value);
+    }
+
+    fn u16(&mut self, value: u16) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn u64(&mut self, value: u64) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn bytes(&mut self, bytes: &[u8]) {
+        self.0.extend_from_slice(bytes);
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], StateError> {
+        let bytes = self.data.get(self.pos..self.pos + len).ok_or(StateError::Truncated)?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, StateError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, StateError> {
+        Ok(u16::from_le_bytes(self.bytes(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32, StateError> {
+        Ok(u32::from_le_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, StateError> {
+        Ok(u64::from_le_bytes(self.bytes(8)?.try_into().unwrap()))
+    }
+}
@@ -0,0 +1,163 @@
+/* save states: a round trip on every platform, and each way a state can be
+ * turned down */
+
+use super::*;
+use crate::hash::rom_hash;
+use crate::platform::Platform;
+use crate::platform::PLATFORMS;
+use crate::platform::SCHIP;
+use crate::platform::VIP;
+
+
+/* sets the delay timer, draws a sprite, then calls a subroutine that spins
+ * so there's something in the stack */
+const ROM: [u8; 18] = [
+    0x60, 0x05,     /* 200: V0 = 5 */
+    0xf0, 0x15,     /* 202: DT = V0 */
+    0xa2, 0x10,     /* 204: I = 210 */
+    0xd0, 0x15,     /* 206: draw 5 rows at V0, V0 */
+    0x22, 0x0e,     /* 208: call 20E */
+    0x12, 0x0a,     /* 20A: jump 20A */
+    0x00, 0x00,
+    0x12, 0x0e,     /* 20E: jump 20E */
+    0xf0, 0x90,     /* 210: sprite */
+];
+
+/* a CPU a few frames into ROM, with a key held and the display in hires on
+ * machines that have it */
+fn running(platform: Platform) -> Cpu {
+    let mut cpu = Cpu::new(platform);
+    cpu.load_rom_bytes(&ROM);
+    if platform.width > WIDTH {
+        cpu.hires = true;
+        cpu.width = platform.width;
+        cpu.height = platform.height;
+        cpu.vram = vec![0; cpu.width * cpu.height];
+    }
+    cpu.keypad.press(0xa);
+
+    for _ in 0..3 {
+        cpu.run_frame().unwrap();
+    }
+    cpu
+}
+
+/* what load_state() makes of a state mangled by change, in a CPU with the
+ * ROM loaded. a state that doesn't load mustn't change anything */
+fn load_mangled(change: impl FnOnce(&mut Vec<u8>)) -> Result<(), StateError> {
+    let mut state = running(VIP).save_state();
+    change(&mut state);
+
+    let mut cpu = Cpu::new(VIP);
+    cpu.load_rom_bytes(&ROM);
+    let before = cpu.save_state();
+
+    let result = cpu.load_state(&state);
+    if result.is_err() {
+        assert_eq!(cpu.save_state(), before);
+    }
+    result
+}
+
+/* where fields are in a VIP state, from the layout in state.rs: the magic,
+ * version and ROM hash, then the platform name "vip" */
+const PLATFORM_NAME: usize = 4 + 2 + 8 + 1;
+const RANDOM_ALGORITHM: usize = PLATFORM_NAME + 3 + 1 + 4;
+const STACK_DEPTH: usize = RANDOM_ALGORITHM + 1 + 8 + 2 + 16 + 4 + 4;
+/* after the 12 stack entries */
+const STACK_POINTER: usize = STACK_DEPTH + 1 + 12 * 2;
+const WIDTH_FIELD: usize = STACK_POINTER + 1 + 1 + 1 + 16 + 1 + 1;
+
+
+#[test]
+fn states_round_trip_on_every_platform() {
+    for platform in PLATFORMS {
+        let cpu = running(platform);
+        let state = cpu.save_state();
+
+        let mut loaded = Cpu::new(VIP);
+        loaded.load_rom_bytes(&ROM);
+        loaded.load_state(&state).unwrap();
+
+        assert_eq!(loaded.save_state(), state, "{}", platform.name);
+        assert_eq!(loaded.platform.name, platform.name);
+        assert_eq!(loaded.stack(), cpu.stack());
+        assert_eq!(loaded.vram, cpu.vram);
+        assert!(loaded.vram_changed);
+    }
+}
+
+#[test]
+fn a_loaded_state_carries_on_the_same() {
+    let mut cpu = running(SCHIP);
+    let state = cpu.save_state();
+    cpu.run_frame().unwrap();
+
+    let mut loaded = Cpu::new(SCHIP);
+    loaded.load_rom_bytes(&ROM);
+    loaded.load_state(&state).unwrap();
+    loaded.run_frame().unwrap();
+
+    assert_eq!(loaded.save_state(), cpu.save_state());
+}
+
+#[test]
+fn the_running_test_state_is_what_the_offsets_expect() {
+    let state = running(VIP).save_state();
+    assert_eq!(&state[PLATFORM_NAME..PLATFORM_NAME + 3], b"vip");
+    assert_eq!(state[RANDOM_ALGORITHM], 0);
+    assert_eq!(state[STACK_DEPTH], 12);
+    assert_eq!(state[STACK_POINTER], 1);
+    assert_eq!(&state[WIDTH_FIELD..WIDTH_FIELD + 4], &[64, 0, 32, 0]);
+}
+
+#[test]
+fn not_a_state() {
+    assert_eq!(load_mangled(|state| state[0] = b'X'), Err(StateError::NotAState));
+    assert_eq!(load_mangled(|state| state.clear()), Err(StateError::NotAState));
+}
+
+#[test]
+fn newer_versions_are_refused() {
+    let newer = VERSION + 1;
+    let result = load_mangled(|state| state[4..6].copy_from_slice(&newer.to_le_bytes()));
+    assert_eq!(result, Err(StateError::UnsupportedVersion(newer)));
+}
+
+#[test]
+fn states_only_load_into_their_rom() {
+    let state = running(VIP).save_state();
+
+    let mut cpu = Cpu::new(VIP);
+    cpu.load_rom_bytes(&ROM[..16]);
+    let err = cpu.load_state(&state).unwrap_err();
+    assert_eq!(err, StateError::WrongRom { loaded: rom_hash(&ROM[..16]), saved: rom_hash(&ROM) });
+}
+
+#[test]
+fn truncated_states_are_refused() {
+    assert_eq!(load_mangled(|state| state.truncate(20)), Err(StateError::Truncated));
+    assert_eq!(load_mangled(|state| { state.pop(); }), Err(StateError::Truncated));
+}
+
+#[test]
+fn corrupt_fields_are_refused() {
+    assert_eq!(load_mangled(|state| state[PLATFORM_NAME] = b'x'),
+               Err(StateError::Corrupt("platform")));
+    assert_eq!(load_mangled(|state| state[RANDOM_ALGORITHM] = 7),
+               Err(StateError::Corrupt("random algorithm")));
+    assert_eq!(load_mangled(|state| state[STACK_DEPTH] = 16),
+               Err(StateError::Corrupt("stack depth")));
+    assert_eq!(load_mangled(|state| state[STACK_POINTER] = 13),
+               Err(StateError::Corrupt("stack pointer")));
+    /* a hires display on the VIP */
+    assert_eq!(load_mangled(|state| state[WIDTH_FIELD] = 128),
+               Err(StateError::Corrupt("display size")));
+
+    /* the RAM's length comes right before the RAM, at the end */
+    let result = load_mangled(|state| {
+        let at = state.len() - 4096 - 4;
+        state[at..at + 4].copy_from_slice(&8192u32.to_le_bytes());
+    });
+    assert_eq!(result, Err(StateError::Corrupt("memory size")));
+}
@@ -0,0 +1,635 @@
+/* one or a few tests per opcode, each on a CPU built to order with Setup */
+
+use super::*;
+use crate::platform::CHIP48;
+use crate::platform::SCHIP;
+use crate::platform::VIP;
+use crate::platform::XOCHIP;
+use crate::rng::RandomAlgorithm;
+
+
+/* a CPU for a single test: the registers, memory and program it needs and
+ * nothing else. every quirk starts off so a test only sees the ones it
+ * turns on */
+struct Setup {
+    cpu: Cpu,
+}
+
+fn setup() -> Setup {
+    Setup::on(VIP)
+}
+
+impl Setup {
+    fn on(platform: Platform) -> Setup {
+        let mut cpu = Cpu::new(platform);
+        cpu.quirks = Quirks::default();
+        Setup { cpu }
+    }
+
+    fn quirks(mut self, set: impl FnOnce(&mut Quirks)) -> Setup {
+        set(&mut self.cpu.quirks);
+        self
+    }
+
+    fn v(mut self, x: usize, value: u8) -> Setup {
+        self.cpu.v[x] = value;
+        self
+    }
+
+    fn i(mut self, addr: usize) -> Setup {
+        self.cpu.i = addr;
+        self
+    }
+
+    /* where the program goes, 0x200 otherwise */
+    fn pc(mut self, addr: usize) -> Setup {
+        self.cpu.pc = addr;
+        self
+    }
+
+    fn ram(mut self, addr: usize, bytes: &[u8]) -> Setup {
+        self.cpu.ram[addr..addr + bytes.len()].copy_from_slice(bytes);
+        self
+    }
+
+    fn keys(mut self, keys: &[usize]) -> Setup {
+        for &key in keys {
+            self.cpu.keypad.press(key);
+        }
+        self
+    }
+
+    /* opcodes to put at pc */
+    fn program(self, opcodes: &[u16]) -> Setup {
+        let pc = self.cpu.pc;
+        let bytes: Vec<u8> = opcodes.iter().flat_map(|op| op.to_be_bytes()).collect();
+        self.ram(pc, &bytes)
+    }
+
+    /* run cycles instructions, failing the test on an error */
+    fn run(mut self, cycles: usize) -> Cpu {
+        for _ in 0..cycles {
+            if let Err(err) = self.cpu.emulate_cycle() {
+                panic!("{}", err);
+            }
+        }
+        self.cpu
+    }
+
+    /* run a single opcode */
+    fn exec(self, opcode: u16) -> Cpu {
+        self.program(&[opcode]).run(1)
+    }
+
+    /* run a single opcode that has to fail */
+    fn fails(mut self, opcode: u16) -> CpuError {
+        self = self.program(&[opcode]);
+        match self.cpu.emulate_cycle() {
+            Ok(_) => panic!("{:04X} didn't fail", opcode),
+            Err(err) => err,
+        }
+    }
+}
+
+fn pixel(cpu: &Cpu, x: usize, y: usize) -> u8 {
+    cpu.vram[y * cpu.width + x]
+}
+
+fn lit(cpu: &Cpu) -> usize {
+    cpu.vram.iter().filter(|&&p| p != 0).count()
+}
+
+
+#[test]
+fn op_0000_does_nothing() {
+    let cpu = setup().exec(0x0000);
+    assert_eq!(cpu.pc, 0x202);
+}
+
+#[test]
+fn op_00e0_clears_the_selected_planes() {
+    let mut cpu = Setup::on(XOCHIP).program(&[0xf201, 0x00e0]).run(0);
+    cpu.vram.fill(0x03);
+    let cpu = Setup { cpu }.run(2);
+    assert!(cpu.vram.iter().all(|&p| p == 0x01));
+    assert!(cpu.vram_changed);
+}
+
+#[test]
+fn op_2nnn_and_00ee_call_and_return() {
+    let cpu = setup().pc(0x300).exec(0x2400);
+    assert_eq!(cpu.pc, 0x400);
+    assert_eq!(cpu.stack(), &[0x302]);
+
+    let cpu = Setup { cpu }.program(&[0x2500, 0x00ee]).run(1);
+    assert_eq!(cpu.stack(), &[0x302, 0x402]);
+    let cpu = Setup { cpu }.pc(0x500).program(&[0x00ee]).run(1);
+    assert_eq!(cpu.pc, 0x402);
+    assert_eq!(cpu.stack(), &[0x302]);
+}
+
+#[test]
+fn op_00ee_with_an_empty_stack_underflows() {
+    assert_eq!(setup().fails(0x00ee), CpuError::StackUnderflow { pc: 0x200 });
+}
+
+#[test]
+fn op_2nnn_with_a_full_stack_overflows() {
+    /* calling itself fills the VIP's 12 entries */
+    let cpu = setup().run(0);
+    let depth = cpu.stack.len();
+    let cpu = Setup { cpu }.exec(0x2200);
+    let cpu = Setup { cpu }.run(depth - 1);
+    assert_eq!(cpu.stack().len(), depth);
+    assert_eq!(Setup { cpu }.fails(0x2200), CpuError::StackOverflow { pc: 0x200 });
+}
+
+#[test]
+fn op_2nnn_at_the_top_of_memory_wraps_the_return_address() {
+    let cpu = Setup::on(XOCHIP).pc(0xfffe).exec(0x2300);
+    assert_eq!(cpu.stack(), &[0x0000]);
+}
+
+#[test]
+fn op_00cn_scrolls_down() {
+    let mut cpu = Setup::on(SCHIP).run(0);
+    cpu.vram[0] = 1;
+    let cpu = Setup { cpu }.exec(0x00c3);
+    assert_eq!(pixel(&cpu, 0, 3), 1);
+    assert_eq!(lit(&cpu), 1);
+}
+
+#[test]
+fn op_00dn_scrolls_up() {
+    let mut cpu = Setup::on(XOCHIP).run(0);
+    cpu.vram[5 * cpu.width] = 1;
+    cpu.vram[0] = 1;
+    let cpu = Setup { cpu }.exec(0x00d2);
+    assert_eq!(pixel(&cpu, 0, 3), 1);
+    assert_eq!(lit(&cpu), 1);
+}
+
+#[test]
+fn op_00fb_and_00fc_scroll_four_pixels() {
+    let mut cpu = Setup::on(SCHIP).run(0);
+    cpu.vram[10] = 1;
+    let cpu = Setup { cpu }.exec(0x00fb);
+    assert_eq!(pixel(&cpu, 14, 0), 1);
+
+    let cpu = Setup { cpu }.program(&[0x00fc, 0x00fc, 0x00fc, 0x00fc]).run(4);
+    assert_eq!(lit(&cpu), 0);
+}
+
+#[test]
+fn op_00fd_halts() {
+    let cpu = Setup::on(SCHIP).program(&[0x00fd, 0x6001]).run(2);
+    assert!(cpu.halted);
+    assert_eq!(cpu.pc, 0x200);
+    assert_eq!(cpu.v[0], 0);
+}
+
+#[test]
+fn op_00ff_and_00fe_switch_resolution() {
+    let cpu = Setup::on(SCHIP).exec(0x00ff);
+    assert_eq!((cpu.width, cpu.height), (128, 64));
+    assert_eq!(cpu.vram.len(), 128 * 64);
+
+    let cpu = Setup { cpu }.exec(0x00fe);
+    assert_eq!((cpu.width, cpu.height), (64, 32));
+}
+
+#[test]
+fn later_opcodes_are_invalid_on_the_vip() {
+    assert_eq!(setup().fails(0x00ff), CpuError::InvalidOpcode { pc: 0x200, opcode: 0x00ff });
+    assert_eq!(setup().fails(0x5122), CpuError::InvalidOpcode { pc: 0x200, opcode: 0x5122 });
+    assert_eq!(setup().fails(0x5121), CpuError::InvalidOpcode { pc: 0x200, opcode: 0x5121 });
+}
+
+#[test]
+fn op_1nnn_jumps() {
+    assert_eq!(setup().exec(0x1abc).pc, 0xabc);
+}
+
+#[test]
+fn op_3xnn_skips_if_equal() {
+    assert_eq!(setup().v(3, 0x42).exec(0x3342).pc, 0x204);
+    assert_eq!(setup().v(3, 0x41).exec(0x3342).pc, 0x202);
+}
+
+#[test]
+fn op_4xnn_skips_if_not_equal() {
+    assert_eq!(setup().v(3, 0x42).exec(0x4342).pc, 0x202);
+    assert_eq!(setup().v(3, 0x41).exec(0x4342).pc, 0x204);
+}
+
+#[test]
+fn op_5xy0_skips_if_registers_equal() {
+    assert_eq!(setup().v(1, 7).v(2, 7).exec(0x5120).pc, 0x204);
+    assert_eq!(setup().v(1, 7).v(2, 8).exec(0x5120).pc, 0x202);
+}
+
+#[test]
+fn op_9xy0_skips_if_registers_differ() {
+    assert_eq!(setup().v(1, 7).v(2, 7).exec(0x9120).pc, 0x202);
+    assert_eq!(setup().v(1, 7).v(2, 8).exec(0x9120).pc, 0x204);
+}
+
+#[test]
+fn skips_step_over_f000_whole() {
+    let cpu = Setup::on(XOCHIP).program(&[0x3000, 0xf000, 0x1234]).run(1);
+    assert_eq!(cpu.pc, 0x206);
+}
+
+#[test]
+fn op_5xy2_stores_a_register_range() {
+    let cpu = Setup::on(XOCHIP).v(1, 1).v(2, 2).v(3, 3).i(0x400).exec(0x5132);
+    assert_eq!(&cpu.ram[0x400..0x403], &[1, 2, 3]);
+
+    let cpu = Setup::on(XOCHIP).v(1, 1).v(2, 2).v(3, 3).i(0x400).exec(0x5312);
+    assert_eq!(&cpu.ram[0x400..0x403], &[3, 2, 1]);
+    assert_eq!(cpu.i, 0x400);
+}
+
+#[test]
+fn op_5xy3_loads_a_register_range() {
+    let cpu = Setup::on(XOCHIP).ram(0x400, &[1, 2, 3]).i(0x400).exec(0x5133);
+    assert_eq!(&cpu.v[1..4], &[1, 2, 3]);
+
+    let cpu = Setup::on(XOCHIP).ram(0x400, &[1, 2, 3]).i(0x400).exec(0x5313);
+    assert_eq!(&cpu.v[1..4], &[3, 2, 1]);
+}
+
+#[test]
+fn op_6xnn_sets() {
+    assert_eq!(setup().exec(0x6a5c).v[0xa], 0x5c);
+}
+
+#[test]
+fn op_7xnn_adds_without_carry() {
+    let cpu = setup().v(1, 0xff).v(0xf, 7).exec(0x7102);
+    assert_eq!(cpu.v[1], 0x01);
+    assert_eq!(cpu.v[0xf], 7);
+}
+
+#[test]
+fn op_8xy0_copies() {
+    assert_eq!(setup().v(2, 9).exec(0x8120).v[1], 9);
+}
+
+#[test]
+fn op_8xy1_8xy2_8xy3_logic() {
+    for (opcode, result) in [(0x8121, 0b1110), (0x8122, 0b1000), (0x8123, 0b0110)] {
+        let cpu = setup().v(1, 0b1100).v(2, 0b1010).v(0xf, 5).exec(opcode);
+        assert_eq!(cpu.v[1], result);
+        assert_eq!(cpu.v[0xf], 5);
+
+        let cpu = setup().quirks(|q| q.vf_reset = true).v(1, 0b1100).v(2, 0b1010).v(0xf, 5).exec(opcode);
+        assert_eq!(cpu.v[1], result);
+        assert_eq!(cpu.v[0xf], 0);
+    }
+}
+
+#[test]
+fn op_8xy4_adds_with_carry() {
+    let cpu = setup().v(1, 0xf0).v(2, 0x20).exec(0x8124);
+    assert_eq!((cpu.v[1], cpu.v[0xf]), (0x10, 1));
+
+    let cpu = setup().v(1, 0x10).v(2, 0x20).v(0xf, 1).exec(0x8124);
+    assert_eq!((cpu.v[1], cpu.v[0xf]), (0x30, 0));
+
+    let cpu = setup().v(1, 0xff).v(2, 0x01).exec(0x8124);
+    assert_eq!((cpu.v[1], cpu.v[0xf]), (0x00, 1));
+}
+
+#[test]
+fn op_8xy5_subtracts_with_borrow() {
+    let cpu = setup().v(1, 0x30).v(2, 0x10).exec(0x8125);
+    assert_eq!((cpu.v[1], cpu.v[0xf]), (0x20, 1));
+
+    let cpu = setup().v(1, 0x10).v(2, 0x30).v(0xf, 1).exec(0x8125);
+    assert_eq!((cpu.v[1], cpu.v[0xf]), (0xe0, 0));
+
+    /* equal is no borrow */
+    let cpu = setup().v(1, 0x10).v(2, 0x10).exec(0x8125);
+    assert_eq!((cpu.v[1], cpu.v[0xf]), (0x00, 1));
+}
+
+#[test]
+fn op_8xy7_subtracts_reversed_with_borrow() {
+    let cpu = setup().v(1, 0x10).v(2, 0x30).exec(0x8127);
+    assert_eq!((cpu.v[1], cpu.v[0xf]), (0x20, 1));
+
+    let cpu = setup().v(1, 0x30).v(2, 0x10).v(0xf, 1).exec(0x8127);
+    assert_eq!((cpu.v[1], cpu.v[0xf]), (0xe0, 0));
+
+    let cpu = setup().v(1, 0x10).v(2, 0x10).exec(0x8127);
+    assert_eq!((cpu.v[1], cpu.v[0xf]), (0x00, 1));
+}
+
+#[test]
+fn arithmetic_flag_wins_when_vx_is_vf() {
+    /* 0xf0 + 0x20 carries */
+    assert_eq!(setup().v(0xf, 0xf0).v(2, 0x20).exec(0x8f24).v[0xf], 1);
+    assert_eq!(setup().v(0xf, 0x10).v(2, 0x20).exec(0x8f24).v[0xf], 0);
+    /* 0x10 - 0x30 borrows */
+    assert_eq!(setup().v(0xf, 0x10).v(2, 0x30).exec(0x8f25).v[0xf], 0);
+    assert_eq!(setup().v(0xf, 0x30).v(2, 0x10).exec(0x8f25).v[0xf], 1);
+    /* 0x30 - 0x10 doesn't */
+    assert_eq!(setup().v(0xf, 0x10).v(2, 0x30).exec(0x8f27).v[0xf], 1);
+    assert_eq!(setup().v(0xf, 0x30).v(2, 0x10).exec(0x8f27).v[0xf], 0);
+    /* the bit shifted out */
+    assert_eq!(setup().v(0xf, 0x02).exec(0x8f06).v[0xf], 0);
+    assert_eq!(setup().v(0xf, 0x40).exec(0x8f0e).v[0xf], 0);
+    assert_eq!(setup().v(0xf, 0x81).exec(0x8f0e).v[0xf], 1);
+}
+
+#[test]
+fn op_8xy6_shifts_right() {
+    let cpu = setup().v(1, 0b0000_0101).v(2, 0b1000_0000).exec(0x8126);
+    assert_eq!((cpu.v[1], cpu.v[0xf]), (0b0000_0010, 1));
+
+    let cpu = setup().quirks(|q| q.shift_uses_vy = true)
+                     .v(1, 0b0000_0101).v(2, 0b1000_0000).exec(0x8126);
+    assert_eq!((cpu.v[1], cpu.v[0xf]), (0b0100_0000, 0));
+}
+
+#[test]
+fn op_8xye_shifts_left() {
+    let cpu = setup().v(1, 0b1000_0001).v(2, 0b0000_0001).exec(0x812e);
+    assert_eq!((cpu.v[1], cpu.v[0xf]), (0b0000_0010, 1));
+
+    let cpu = setup().quirks(|q| q.shift_uses_vy = true)
+                     .v(1, 0b1000_0001).v(2, 0b0000_0001).exec(0x812e);
+    assert_eq!((cpu.v[1], cpu.v[0xf]), (0b0000_0010, 0));
+}
+
+#[test]
+fn op_annn_sets_i() {
+    assert_eq!(setup().exec(0xa123).i, 0x123);
+}
+
+#[test]
+fn op_bnnn_jumps_to_nnn_plus_v0() {
+    /* from somewhere other than 0x200, to tell an assignment from adding
+     * to pc */
+    let cpu = setup().pc(0x400).v(0, 0x10).v(3, 0x20).exec(0xb300);
+    assert_eq!(cpu.pc, 0x310);
+
+    let cpu = setup().quirks(|q| q.jump_with_vx = true).pc(0x400).v(0, 0x10).v(3, 0x20).exec(0xb300);
+    assert_eq!(cpu.pc, 0x320);
+}
+
+#[test]
+fn op_cxnn_masks_random_numbers() {
+    assert_eq!(setup().v(1, 0xff).exec(0xc100).v[1], 0);
+
+    let mut cpu = setup().program(&[0xc10f, 0x1200]).run(0);
+    let mut values = Vec::new();
+    for _ in 0..32 {
+        cpu = Setup { cpu }.run(2);
+        values.push(cpu.v[1]);
+    }
+    assert!(values.iter().all(|&v| v <= 0x0f));
+    assert!(values.iter().any(|&v| v != values[0]));
+}
+
+#[test]
+fn op_cxnn_repeats_with_the_same_seed() {
+    let run = |seed| {
+        let mut cpu = setup().program(&[0xc1ff, 0x1200]).run(0);
+        cpu.set_rng(Rng::new(RandomAlgorithm::SplitMix, seed));
+        let mut values = Vec::new();
+        for _ in 0..16 {
+            cpu = Setup { cpu }.run(2);
+            values.push(cpu.v[1]);
+        }
+        values
+    };
+
+    assert_eq!(run(42), run(42));
+    assert_ne!(run(42), run(43));
+}
+
+#[test]
+fn op_dxyn_draws_and_detects_collisions() {
+    let cpu = setup().ram(0x300, &[0b1100_0000, 0b0100_0000]).i(0x300).v(1, 4).v(2, 8).exec(0xd122);
+    assert_eq!((pixel(&cpu, 4, 8), pixel(&cpu, 5, 8), pixel(&cpu, 5, 9)), (1, 1, 1));
+    assert_eq!(lit(&cpu), 3);
+    assert_eq!(cpu.v[0xf], 0);
+    assert!(cpu.vram_changed);
+
+    /* drawing it again erases it and collides */
+    let cpu = Setup { cpu }.pc(0x200).exec(0xd122);
+    assert_eq!(lit(&cpu), 0);
+    assert_eq!(cpu.v[0xf], 1);
+}
+
+#[test]
+fn op_dxyn_collision_only_counts_pixels_turned_off() {
+    let cpu = setup().ram(0x300, &[0b1000_0000, 0b0100_0000]).i(0x300).exec(0xd011);
+    let cpu = Setup { cpu }.pc(0x200).i(0x301).exec(0xd011);
+    assert_eq!(lit(&cpu), 2);
+    assert_eq!(cpu.v[0xf], 0);
+}
+
+#[test]
+fn op_dxyn_wraps_the_start_and_clips_or_wraps_the_rest() {
+    /* x 66 wraps to 2 */
+    let cpu = setup().ram(0x300, &[0x80]).i(0x300).v(1, 66).v(2, 1).exec(0xd121);
+    assert_eq!(pixel(&cpu, 2, 1), 1);
+
+    let cpu = setup().quirks(|q| q.clip_sprites = true).ram(0x300, &[0xff]).i(0x300).v(1, 60).exec(0xd101);
+    assert_eq!(lit(&cpu), 4);
+
+    let cpu = setup().ram(0x300, &[0xff]).i(0x300).v(1, 60).exec(0xd101);
+    assert_eq!(lit(&cpu), 8);
+    assert_eq!(pixel(&cpu, 0, 0), 1);
+}
+
+#[test]
+fn op_dxyn_waits_for_vblank_with_the_quirk() {
+    let cpu = setup().quirks(|q| q.display_wait = true).program(&[0xd001, 0x6001]).run(2);
+    assert_eq!(cpu.v[0], 0);
+
+    let mut cpu = cpu;
+    cpu.vblank();
+    let cpu = Setup { cpu }.run(1);
+    assert_eq!(cpu.v[0], 1);
+}
+
+#[test]
+fn op_dxy0_draws_16x16_on_superchip_hires_and_xochip() {
+    let cpu = Setup::on(SCHIP).ram(0x300, &[0xff; 32]).i(0x300).program(&[0x00ff, 0xd000]).run(2);
+    assert_eq!(lit(&cpu), 256);
+
+    let cpu = Setup::on(XOCHIP).ram(0x300, &[0xff; 32]).i(0x300).exec(0xd000);
+    assert_eq!(lit(&cpu), 256);
+}
+
+#[test]
+fn op_dxy0_draws_8x16_on_superchip_lores() {
+    let cpu = Setup::on(SCHIP).ram(0x300, &[0xff; 32]).i(0x300).exec(0xd000);
+    assert_eq!(lit(&cpu), 128);
+    assert_eq!((pixel(&cpu, 7, 15), pixel(&cpu, 8, 0)), (1, 0));
+
+    /* and nothing on the VIP */
+    let cpu = setup().ram(0x300, &[0xff; 32]).i(0x300).exec(0xd000);
+    assert_eq!(lit(&cpu), 0);
+}
+
+#[test]
+fn op_dxyn_counts_rows_in_superchip_hires() {
+    let cpu = Setup::on(SCHIP).ram(0x300, &[0xff; 3]).i(0x300).program(&[0x00ff, 0xd003, 0xd003]).run(3);
+    assert_eq!(cpu.v[0xf], 3);
+}
+
+#[test]
+fn op_dxyn_past_the_end_of_memory_fails() {
+    let err = setup().i(0xffe).fails(0xd004);
+    assert_eq!(err, CpuError::MemoryOutOfBounds { pc: 0x200, addr: 0x1000 });
+}
+
+#[test]
+fn op_ex9e_and_exa1_skip_on_keys() {
+    assert_eq!(setup().v(1, 0xa).keys(&[0xa]).exec(0xe19e).pc, 0x204);
+    assert_eq!(setup().v(1, 0xa).exec(0xe19e).pc, 0x202);
+    assert_eq!(setup().v(1, 0xa).keys(&[0xa]).exec(0xe1a1).pc, 0x202);
+    assert_eq!(setup().v(1, 0xa).exec(0xe1a1).pc, 0x204);
+}
+
+#[test]
+fn op_f000_loads_a_long_i() {
+    let cpu = Setup::on(XOCHIP).program(&[0xf000, 0xbeef]).run(1);
+    assert_eq!((cpu.i, cpu.pc), (0xbeef, 0x204));
+}
+
+#[test]
+fn op_fn01_selects_planes() {
+    let cpu = Setup::on(XOCHIP).ram(0x300, &[0x80, 0x80]).i(0x300).program(&[0xf301, 0xd001]).run(2);
+    assert_eq!(pixel(&cpu, 0, 0), 0x03);
+}
+
+#[test]
+fn op_f002_loads_the_audio_pattern() {
+    let pattern: Vec<u8> = (0..16).collect();
+    let cpu = Setup::on(XOCHIP).ram(0x300, &pattern).i(0x300).exec(0xf002);
+    assert_eq!(&cpu.pattern.unwrap()[..], &pattern[..]);
+}
+
+#[test]
+fn timer_opcodes() {
+    let cpu = setup().v(1, 30).v(2, 40).program(&[0xf115, 0xf218]).run(2);
+    assert_eq!((cpu.delay_timer, cpu.sound_timer), (30, 40));
+    assert!(cpu.output().beep);
+
+    let mut cpu = Setup { cpu }.program(&[0xf307]).run(0);
+    cpu.vblank();
+    let cpu = Setup { cpu }.run(1);
+    assert_eq!(cpu.v[3], 29);
+}
+
+#[test]
+fn op_fx0a_waits_for_a_press_and_release() {
+    let cpu = setup().program(&[0xf10a]).run(3);
+    assert_eq!(cpu.pc, 0x200);
+
+    let cpu = Setup { cpu }.keys(&[0x7]).run(3);
+    assert_eq!(cpu.pc, 0x200);
+
+    let mut cpu = cpu;
+    cpu.keypad.release(0x7);
+    let cpu = Setup { cpu }.run(1);
+    assert_eq!((cpu.pc, cpu.v[1]), (0x202, 0x7));
+}
+
+#[test]
+fn op_fx1e_adds_to_i_and_leaves_vf_alone() {
+    let cpu = setup().i(0x100).v(1, 0x20).v(0xf, 7).exec(0xf11e);
+    assert_eq!((cpu.i, cpu.v[0xf]), (0x120, 7));
+
+    /* on every platform, as they come */
+    for platform in [VIP, CHIP48, SCHIP, XOCHIP] {
+        let mut cpu = Cpu::new(platform);
+        cpu.pc = 0x200;
+        let cpu = Setup { cpu }.i(0xff0).v(1, 0x20).v(0xf, 7).exec(0xf11e);
+        assert_eq!((cpu.i, cpu.v[0xf]), (0x1010, 7));
+    }
+}
+
+#[test]
+fn op_fx1e_sets_vf_past_0xf00_with_the_quirk() {
+    let cpu = setup().quirks(|q| q.index_overflow = true).i(0x100).v(1, 0x20).v(0xf, 7).exec(0xf11e);
+    assert_eq!(cpu.v[0xf], 0);
+
+    let cpu = setup().quirks(|q| q.index_overflow = true).i(0xef0).v(1, 0x20).exec(0xf11e);
+    assert_eq!((cpu.i, cpu.v[0xf]), (0xf10, 1));
+}
+
+#[test]
+fn op_fx29_and_fx30_point_at_the_font() {
+    assert_eq!(setup().v(1, 0xa).exec(0xf129).i, 50);
+    assert_eq!(Setup::on(SCHIP).v(1, 3).exec(0xf130).i, BIG_FONT_ADDR + 30);
+}
+
+#[test]
+fn op_fx33_stores_bcd() {
+    for (value, digits) in [(0, [0, 0, 0]), (7, [0, 0, 7]), (42, [0, 4, 2]), (100, [1, 0, 0]), (255, [2, 5, 5])] {
+        let cpu = setup().v(4, value).i(0x300).exec(0xf433);
+        assert_eq!(&cpu.ram[0x300..0x303], &digits);
+        assert_eq!(cpu.i, 0x300);
+    }
+}
+
+#[test]
+fn op_fx33_past_the_end_of_memory_fails() {
+    let err = setup().i(0xffe).fails(0xf033);
+    assert_eq!(err, CpuError::MemoryOutOfBounds { pc: 0x200, addr: 0x1000 });
+}
+
+#[test]
+fn op_fx3a_sets_the_pitch() {
+    assert_eq!(Setup::on(XOCHIP).v(1, 99).exec(0xf13a).pitch, 99);
+}
+
+#[test]
+fn op_fx55_and_fx65_store_and_load() {
+    let cpu = setup().v(0, 1).v(1, 2).v(2, 3).v(3, 4).i(0x300).exec(0xf255);
+    assert_eq!(&cpu.ram[0x300..0x304], &[1, 2, 3, 0]);
+    assert_eq!(cpu.i, 0x300);
+
+    let cpu = setup().ram(0x300, &[1, 2, 3, 4]).i(0x300).exec(0xf265);
+    assert_eq!(&cpu.v[..4], &[1, 2, 3, 0]);
+
+    let cpu = setup().quirks(|q| q.load_store_increments_i = true).i(0x300).exec(0xf255);
+    assert_eq!(cpu.i, 0x303);
+    let cpu = setup().quirks(|q| q.load_store_increments_i = true).i(0x300).exec(0xf265);
+    assert_eq!(cpu.i, 0x303);
+}
+
+#[test]
+fn op_fx75_and_fx85_save_flags() {
+    let cpu = Setup::on(SCHIP).v(0, 1).v(1, 2).v(2, 3).program(&[0xf175, 0x6000, 0x6100, 0x6200, 0xf285]).run(5);
+    assert_eq!(&cpu.v[..3], &[1, 2, 0]);
+}
+
+#[test]
+fn run_frame_stops_at_an_error_without_ticking_the_timers() {
+    let mut cpu = setup().program(&[0x7101, 0x0123, 0x1200]).run(0);
+    cpu.delay_timer = 5;
+    assert_eq!(cpu.run_frame().err(), Some(CpuError::InvalidOpcode { pc: 0x202, opcode: 0x0123 }));
+    assert_eq!((cpu.v[1], cpu.delay_timer), (1, 5));
+}
+
+#[test]
+fn run_frame_with_carries_on_past_handled_errors() {
+    let mut cpu = setup().program(&[0x7101, 0x0123, 0x1200]).run(0);
+    cpu.delay_timer = 5;
+    cpu.run_frame_with(|cpu, _| {
+        cpu.skip_instruction();
+        Ok(())
+    }).unwrap();
+
+    /* the VIP's 15 instructions a frame, the bad one included, go round the
+     * loop 5 times */
+    assert_eq!((cpu.v[1], cpu.delay_timer), (5, 4));
+}
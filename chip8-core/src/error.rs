@@ -0,0 +1,127 @@
+use std::error::Error;
+use std::fmt;
+
+
+/* why the CPU stopped. pc is the address of the instruction at fault, which
+ * is left unexecuted so a frontend can inspect or skip it */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CpuError {
+    /* CALL with every stack entry in use */
+    StackOverflow { pc: usize },
+    /* RTS with nothing on the stack */
+    StackUnderflow { pc: usize },
+    /* the instruction read or wrote addr, which is past the end of RAM */
+    MemoryOutOfBounds { pc: usize, addr: usize },
+    /* an opcode that doesn't decode, or belongs to a later machine than
+     * the one being emulated */
+    InvalidOpcode { pc: usize, opcode: u16 },
+}
+
+impl CpuError {
+    pub fn pc(&self) -> usize {
+        match *self {
+            CpuError::StackOverflow { pc } => pc,
+            CpuError::StackUnderflow { pc } => pc,
+            CpuError::MemoryOutOfBounds { pc, .. } => pc,
+            CpuError::InvalidOpcode { pc, .. } => pc,
+        }
+    }
+}
+
+impl fmt::Display for CpuError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CpuError::StackOverflow { pc } => {
+                write!(f, "stack overflow at {:04X}", pc)
+            },
+            CpuError::StackUnderflow { pc } => {
+                write!(f, "return with an empty stack at {:04X}", pc)
+            },
+            CpuError::MemoryOutOfBounds { pc, addr } => {
+                write!(f, "memory access out of bounds at {:04X}: address {:04X}", pc, addr)
+            },
+            CpuError::InvalidOpcode { pc, opcode } => {
+                write!(f, "invalid opcode {:04X} at {:04X}", opcode, pc)
+            },
+        }
+    }
+}
+
+impl Error for CpuError {}
+
+/* why a save state couldn't be loaded */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StateError {
+    /* doesn't start with the save state magic number */
+    NotAState,
+    /* written by a newer version of the format */
+    UnsupportedVersion(u16),
+    /* saved from a different ROM to the one loaded. the hashes are the
+     * loaded ROM's and the state's */
+    WrongRom { loaded: u64, saved: u64 },
+    /* the data ends early */
+    Truncated,
+    /* a field has a value the CPU can't be in */
+    Corrupt(&'static str),
+}
+
+impl fmt::Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            StateError::NotAState => write!(f, "not a save state"),
+            StateError::UnsupportedVersion(version) => {
+                write!(f, "save state version {} is newer than this emulator", version)
+            },
+            StateError::WrongRom { loaded, saved } => {
+                write!(f, "save state is for ROM {:016x}, not the loaded {:016x}", saved, loaded)
+            },
+            StateError::Truncated => write!(f, "save state is truncated"),
+            StateError::Corrupt(field) => write!(f, "save state is corrupt: bad {}", field),
+        }
+    }
+}
+
+impl Error for StateError {}
+
+/* why a movie couldn't be loaded or played back */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MovieError {
+    /* doesn't start with the movie magic number */
+    NotAMovie,
+    /* written by a newer version of the format */
+    UnsupportedVersion(u16),
+    /* the data ends early */
+    Truncated,
+    /* the start state wouldn't load, e.g. it's for another ROM */
+    State(StateError),
+    /* the program crashed during playback */
+    Cpu(CpuError),
+}
+
+impl fmt::Display for MovieError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            MovieError::NotAMovie => write!(f, "not a movie"),
+            MovieError::UnsupportedVersion(version) => {
+                write!(f, "movie version {} is newer than this emulator", version)
+            },
+            MovieError::Truncated => write!(f, "movie is truncated"),
+            MovieError::State(err) => write!(f, "movie start state: {}", err),
+            MovieError::Cpu(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl Error for MovieError {}
+
+impl From<StateError> for MovieError {
+    fn from(err: StateError) -> Self {
+        MovieError::State(err)
+    }
+}
+
+impl From<CpuError> for MovieError {
+    fn from(err: CpuError) -> Self {
+        MovieError::Cpu(err)
+    }
+}
@@ -0,0 +1,71 @@
+/* built in hex digit sprites, 0-F, 4x5 pixels each. they're loaded at the
+ * bottom of memory and FX29 points I at them */
+
+/* the font from the COSMAC VIP interpreter ROM */
+pub const VIP_FONT: [u8; 80] = [0xF0, 0x90, 0x90, 0x90, 0xF0, /* 0 */
+                                0x60, 0x20, 0x20, 0x20, 0x70, /* 1 */
+                                0xF0, 0x10, 0xF0, 0x80, 0xF0, /* 2 */
+                                0xF0, 0x10, 0xF0, 0x10, 0xF0, /* 3 */
+                                0xA0, 0xA0, 0xF0, 0x20, 0x20, /* 4 */
+                                0xF0, 0x80, 0xF0, 0x10, 0xF0, /* 5 */
+                                0xF0, 0x80, 0xF0, 0x90, 0xF0, /* 6 */
+                                0xF0, 0x10, 0x10, 0x10, 0x10, /* 7 */
+                                0xF0, 0x90, 0xF0, 0x90, 0xF0, /* 8 */
+                                0xF0, 0x90, 0xF0, 0x10, 0xF0, /* 9 */
+                                0xF0, 0x90, 0xF0, 0x90, 0x90, /* a */
+                                0xF0, 0x50, 0x70, 0x50, 0xF0, /* b */
+                                0xF0, 0x80, 0x80, 0x80, 0xF0, /* c */
+                                0xF0, 0x50, 0x50, 0x50, 0xF0, /* d */
+                                0xF0, 0x80, 0xF0, 0x80, 0xF0, /* e */
+                                0xF0, 0x80, 0xF0, 0x80, 0x80]; /* f */
+
+/* the font CHIP-48 and everything after it ship */
+pub const FONT: [u8; 80] = [0xF0, 0x90, 0x90, 0x90, 0xF0, /* 0 */
+                            0x20, 0x60, 0x20, 0x20, 0x70, /* 1 */
+                            0xF0, 0x10, 0xF0, 0x80, 0xF0, /* 2 */
+                            0xF0, 0x10, 0xF0, 0x10, 0xF0, /* 3 */
+                            0x90, 0x90, 0xF0, 0x10, 0x10, /* 4 */
+                            0xF0, 0x80, 0xF0, 0x10, 0xF0, /* 5 */
+                            0xF0, 0x80, 0xF0, 0x90, 0xF0, /* 6 */
+                            0xF0, 0x10, 0x20, 0x40, 0x40, /* 7 */
+                            0xF0, 0x90, 0xF0, 0x90, 0xF0, /* 8 */
+                            0xF0, 0x90, 0xF0, 0x10, 0xF0, /* 9 */
+                            0xF0, 0x90, 0xF0, 0x90, 0x90, /* a */
+                            0xE0, 0x90, 0xE0, 0x90, 0xE0, /* b */
+                            0xF0, 0x80, 0x80, 0x80, 0xF0, /* c */
+                            0xE0, 0x90, 0x90, 0x90, 0xE0, /* d */
+                            0xF0, 0x80, 0xF0, 0x80, 0xF0, /* e */
+                            0xF0, 0x80, 0xF0, 0x80, 0x80]; /* f */
+
+/* SUPER-CHIP's 8x10 digits for FX30. they go right after the small font */
+pub const BIG_FONT_ADDR: usize = 0x50;
+
+/* SUPER-CHIP 1.1 only has 0-9 */
+pub const SCHIP_BIG_FONT: [u8; 100] = [0x3C, 0x7E, 0xE7, 0xC3, 0xC3, 0xC3, 0xC3, 0xE7, 0x7E, 0x3C, /* 0 */
+                                       0x18, 0x38, 0x58, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x3C, /* 1 */
+                                       0x3E, 0x7F, 0xC3, 0x06, 0x0C, 0x18, 0x30, 0x60, 0xFF, 0xFF, /* 2 */
+                                       0x3C, 0x7E, 0xC3, 0x03, 0x0E, 0x0E, 0x03, 0xC3, 0x7E, 0x3C, /* 3 */
+                                       0x06, 0x0E, 0x1E, 0x36, 0x66, 0xC6, 0xFF, 0xFF, 0x06, 0x06, /* 4 */
+                                       0xFF, 0xFF, 0xC0, 0xC0, 0xFC, 0xFE, 0x03, 0xC3, 0x7E, 0x3C, /* 5 */
+                                       0x3E, 0x7C, 0xC0, 0xC0, 0xFC, 0xFE, 0xC3, 0xC3, 0x7E, 0x3C, /* 6 */
+                                       0xFF, 0xFF, 0x03, 0x06, 0x0C, 0x18, 0x30, 0x60, 0x60, 0x60, /* 7 */
+                                       0x3C, 0x7E, 0xC3, 0xC3, 0x7E, 0x7E, 0xC3, 0xC3, 0x7E, 0x3C, /* 8 */
+                                       0x3C, 0x7E, 0xC3, 0xC3, 0x7F, 0x3F, 0x03, 0x03, 0x3E, 0x7C]; /* 9 */
+
+/* Octo's big font, which has the full 0-F */
+pub const XO_BIG_FONT: [u8; 160] = [0xFF, 0xFF, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, /* 0 */
+                                    0x18, 0x78, 0x78, 0x18, 0x18, 0x18, 0x18, 0x18, 0xFF, 0xFF, /* 1 */
+                                    0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, /* 2 */
+                                    0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, /* 3 */
+                                    0xC3, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0x03, 0x03, 0x03, 0x03, /* 4 */
+                                    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, /* 5 */
+                                    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, /* 6 */
+                                    0xFF, 0xFF, 0x03, 0x03, 0x06, 0x0C, 0x18, 0x18, 0x18, 0x18, /* 7 */
+                                    0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, /* 8 */
+                                    0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, /* 9 */
+                                    0x7E, 0xFF, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xC3, /* a */
+                                    0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, /* b */
+                                    0x3C, 0xFF, 0xC3, 0xC0, 0xC0, 0xC0, 0xC0, 0xC3, 0xFF, 0x3C, /* c */
+                                    0xFC, 0xFE, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFE, 0xFC, /* d */
+                                    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, /* e */
+                                    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xC0, 0xC0]; /* f */
@@ -0,0 +1,9 @@
+/* identifies a ROM whatever its file is called, for per-ROM settings and
+ * checking a save state belongs to the ROM it's loaded into. this is 64-bit
+ * FNV-1a, written out as 16 hex digits */
+pub fn rom_hash(rom: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+
+    rom.iter().fold(OFFSET_BASIS, |hash, &byte| (hash ^ byte as u64).wrapping_mul(PRIME))
+}
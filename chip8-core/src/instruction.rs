@@ -0,0 +1,331 @@
+use crate::platform::Machine;
+
+
+/* a decoded CHIP-8 instruction. this is the single source of truth for the
+ * opcode encoding, both the CPU dispatch and the disassembler go through it.
+ *
+ * X and Y are register indices (0x0-0xF), NN is an 8-bit constant, N a 4-bit
+ * constant and NNN a 12-bit address. see instruction-set.txt for what each one
+ * does */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Instruction {
+    /* 0000 */
+    Nop,
+    /* 00E0 */
+    Cls,
+    /* 00EE */
+    Rts,
+    /* 00CN, SUPER-CHIP */
+    ScrollDown { n: u8 },
+    /* 00DN, XO-CHIP */
+    ScrollUp { n: u8 },
+    /* 00FB, SUPER-CHIP */
+    ScrollRight,
+    /* 00FC, SUPER-CHIP */
+    ScrollLeft,
+    /* 00FD, SUPER-CHIP */
+    Exit,
+    /* 00FE, SUPER-CHIP */
+    Lores,
+    /* 00FF, SUPER-CHIP */
+    Hires,
+    /* 1NNN */
+    Jmp { nnn: u16 },
+    /* 2NNN */
+    Call { nnn: u16 },
+    /* 3XNN */
+    SkipEqImm { x: u8, nn: u8 },
+    /* 4XNN */
+    SkipNeImm { x: u8, nn: u8 },
+    /* 5XY0 */
+    SkipEq { x: u8, y: u8 },
+    /* 5XY2, XO-CHIP */
+    StoreRange { x: u8, y: u8 },
+    /* 5XY3, XO-CHIP */
+    LoadRange { x: u8, y: u8 },
+    /* 6XNN */
+    Mvi { x: u8, nn: u8 },
+    /* 7XNN */
+    Adi { x: u8, nn: u8 },
+    /* 8XY0 */
+    Mov { x: u8, y: u8 },
+    /* 8XY1 */
+    Or { x: u8, y: u8 },
+    /* 8XY2 */
+    And { x: u8, y: u8 },
+    /* 8XY3 */
+    Xor { x: u8, y: u8 },
+    /* 8XY4 */
+    Add { x: u8, y: u8 },
+    /* 8XY5 */
+    Sub { x: u8, y: u8 },
+    /* 8XY6 */
+    Shr { x: u8, y: u8 },
+    /* 8XY7 */
+    Subb { x: u8, y: u8 },
+    /* 8XYE */
+    Shl { x: u8, y: u8 },
+    /* 9XY0 */
+    SkipNe { x: u8, y: u8 },
+    /* ANNN */
+    MviI { nnn: u16 },
+    /* BNNN */
+    JmpV0 { nnn: u16 },
+    /* CXNN */
+    Rndmsk { x: u8, nn: u8 },
+    /* DXYN */
+    Sprite { x: u8, y: u8, n: u8 },
+    /* EX9E */
+    SkipKeyY { x: u8 },
+    /* EXA1 */
+    SkipKeyN { x: u8 },
+    /* F000 NNNN, XO-CHIP. the address is the word after the opcode */
+    LongI,
+    /* FN01, XO-CHIP. N is the bitplane mask, in the X position */
+    Plane { x: u8 },
+    /* F002, XO-CHIP */
+    Audio,
+    /* FX07 */
+    GetDelay { x: u8 },
+    /* FX0A */
+    Key { x: u8 },
+    /* FX15 */
+    SetDelay { x: u8 },
+    /* FX18 */
+    SetSound { x: u8 },
+    /* FX1E */
+    AdiI { x: u8 },
+    /* FX29 */
+    SpriteChar { x: u8 },
+    /* FX30, SUPER-CHIP */
+    BigSpriteChar { x: u8 },
+    /* FX33 */
+    MovBcd { x: u8 },
+    /* FX3A, XO-CHIP */
+    Pitch { x: u8 },
+    /* FX55 */
+    Store { x: u8 },
+    /* FX65 */
+    Load { x: u8 },
+    /* FX75, SUPER-CHIP */
+    StoreFlags { x: u8 },
+    /* FX85, SUPER-CHIP */
+    LoadFlags { x: u8 },
+}
+
+impl Instruction {
+    /* decode a big-endian opcode. returns None for anything that isn't a
+     * valid instruction, which in a ROM is usually just sprite data */
+    pub fn decode(opcode: u16) -> Option<Instruction> {
+        let nibbles = (
+                (opcode & 0xf000) >> 12,
+                (opcode & 0x0f00) >> 8,
+                (opcode & 0x00f0) >> 4,
+                opcode & 0x000f,
+        );
+
+        let nnn = opcode & 0x0fff;
+        let nn = (opcode & 0x00ff) as u8;
+        let x = nibbles.1 as u8;
+        let y = nibbles.2 as u8;
+        let n = nibbles.3 as u8;
+
+        let instr = match nibbles {
+            (0x00, 0x00, 0x00, 0x00) => Instruction::Nop,
+            (0x00, 0x00, 0x0e, 0x00) => Instruction::Cls,
+            (0x00, 0x00, 0x0e, 0x0e) => Instruction::Rts,
+            (0x00, 0x00, 0x0c, _) => Instruction::ScrollDown { n },
+            (0x00, 0x00, 0x0d, _) => Instruction::ScrollUp { n },
+            (0x00, 0x00, 0x0f, 0x0b) => Instruction::ScrollRight,
+            (0x00, 0x00, 0x0f, 0x0c) => Instruction::ScrollLeft,
+            (0x00, 0x00, 0x0f, 0x0d) => Instruction::Exit,
+            (0x00, 0x00, 0x0f, 0x0e) => Instruction::Lores,
+            (0x00, 0x00, 0x0f, 0x0f) => Instruction::Hires,
+            (0x01, _, _, _) => Instruction::Jmp { nnn },
+            (0x02, _, _, _) => Instruction::Call { nnn },
+            (0x03, _, _, _) => Instruction::SkipEqImm { x, nn },
+            (0x04, _, _, _) => Instruction::SkipNeImm { x, nn },
+            (0x05, _, _, 0x00) => Instruction::SkipEq { x, y },
+            (0x05, _, _, 0x02) => Instruction::StoreRange { x, y },
+            (0x05, _, _, 0x03) => Instruction::LoadRange { x, y },
+            (0x06, _, _, _) => Instruction::Mvi { x, nn },
+            (0x07, _, _, _) => Instruction::Adi { x, nn },
+            (0x08, _, _, 0x00) => Instruction::Mov { x, y },
+            (0x08, _, _, 0x01) => Instruction::Or { x, y },
+            (0x08, _, _, 0x02) => Instruction::And { x, y },
+            (0x08, _, _, 0x03) => Instruction::Xor { x, y },
+            (0x08, _, _, 0x04) => Instruction::Add { x, y },
+            (0x08, _, _, 0x05) => Instruction::Sub { x, y },
+            (0x08, _, _, 0x06) => Instruction::Shr { x, y },
+            (0x08, _, _, 0x07) => Instruction::Subb { x, y },
+            (0x08, _, _, 0x0e) => Instruction::Shl { x, y },
+            (0x09, _, _, 0x00) => Instruction::SkipNe { x, y },
+            (0x0a, _, _, _) => Instruction::MviI { nnn },
+            (0x0b, _, _, _) => Instruction::JmpV0 { nnn },
+            (0x0c, _, _, _) => Instruction::Rndmsk { x, nn },
+            (0x0d, _, _, _) => Instruction::Sprite { x, y, n },
+            (0x0e, _, 0x09, 0x0e) => Instruction::SkipKeyY { x },
+            (0x0e, _, 0x0a, 0x01) => Instruction::SkipKeyN { x },
+            (0x0f, 0x00, 0x00, 0x00) => Instruction::LongI,
+            (0x0f, _, 0x00, 0x01) => Instruction::Plane { x },
+            (0x0f, 0x00, 0x00, 0x02) => Instruction::Audio,
+            (0x0f, _, 0x00, 0x07) => Instruction::GetDelay { x },
+            (0x0f, _, 0x00, 0x0a) => Instruction::Key { x },
+            (0x0f, _, 0x01, 0x05) => Instruction::SetDelay { x },
+            (0x0f, _, 0x01, 0x08) => Instruction::SetSound { x },
+            (0x0f, _, 0x01, 0x0e) => Instruction::AdiI { x },
+            (0x0f, _, 0x02, 0x09) => Instruction::SpriteChar { x },
+            (0x0f, _, 0x03, 0x00) => Instruction::BigSpriteChar { x },
+            (0x0f, _, 0x03, 0x03) => Instruction::MovBcd { x },
+            (0x0f, _, 0x03, 0x0a) => Instruction::Pitch { x },
+            (0x0f, _, 0x05, 0x05) => Instruction::Store { x },
+            (0x0f, _, 0x06, 0x05) => Instruction::Load { x },
+            (0x0f, _, 0x07, 0x05) => Instruction::StoreFlags { x },
+            (0x0f, _, 0x08, 0x05) => Instruction::LoadFlags { x },
+            _ => return None,
+        };
+
+        Some(instr)
+    }
+
+    /* encode back into the opcode. decode(encode(i)) == Some(i) for every
+     * instruction with in-range operands */
+    pub fn encode(&self) -> u16 {
+        let xy = |op: u16, x: u8, y: u8, n: u16| {
+            op << 12 | (x as u16 & 0xf) << 8 | (y as u16 & 0xf) << 4 | n
+        };
+        let xnn = |op: u16, x: u8, nn: u8| {
+            op << 12 | (x as u16 & 0xf) << 8 | nn as u16
+        };
+        let nnn = |op: u16, nnn: u16| op << 12 | (nnn & 0x0fff);
+
+        match *self {
+            Instruction::Nop => 0x0000,
+            Instruction::Cls => 0x00e0,
+            Instruction::Rts => 0x00ee,
+            Instruction::ScrollDown { n } => 0x00c0 | (n as u16 & 0xf),
+            Instruction::ScrollUp { n } => 0x00d0 | (n as u16 & 0xf),
+            Instruction::ScrollRight => 0x00fb,
+            Instruction::ScrollLeft => 0x00fc,
+            Instruction::Exit => 0x00fd,
+            Instruction::Lores => 0x00fe,
+            Instruction::Hires => 0x00ff,
+            Instruction::Jmp { nnn: a } => nnn(0x1, a),
+            Instruction::Call { nnn: a } => nnn(0x2, a),
+            Instruction::SkipEqImm { x, nn } => xnn(0x3, x, nn),
+            Instruction::SkipNeImm { x, nn } => xnn(0x4, x, nn),
+            Instruction::SkipEq { x, y } => xy(0x5, x, y, 0x0),
+            Instruction::StoreRange { x, y } => xy(0x5, x, y, 0x2),
+            Instruction::LoadRange { x, y } => xy(0x5, x, y, 0x3),
+            Instruction::Mvi { x, nn } => xnn(0x6, x, nn),
+            Instruction::Adi { x, nn } => xnn(0x7, x, nn),
+            Instruction::Mov { x, y } => xy(0x8, x, y, 0x0),
+            Instruction::Or { x, y } => xy(0x8, x, y, 0x1),
+            Instruction::And { x, y } => xy(0x8, x, y, 0x2),
+            Instruction::Xor { x, y } => xy(0x8, x, y, 0x3),
+            Instruction::Add { x, y } => xy(0x8, x, y, 0x4),
+            Instruction::Sub { x, y } => xy(0x8, x, y, 0x5),
+            Instruction::Shr { x, y } => xy(0x8, x, y, 0x6),
+            Instruction::Subb { x, y } => xy(0x8, x, y, 0x7),
+            Instruction::Shl { x, y } => xy(0x8, x, y, 0xe),
+            Instruction::SkipNe { x, y } => xy(0x9, x, y, 0x0),
+            Instruction::MviI { nnn: a } => nnn(0xa, a),
+            Instruction::JmpV0 { nnn: a } => nnn(0xb, a),
+            Instruction::Rndmsk { x, nn } => xnn(0xc, x, nn),
+            Instruction::Sprite { x, y, n } => xy(0xd, x, y, n as u16 & 0xf),
+            Instruction::SkipKeyY { x } => xnn(0xe, x, 0x9e),
+            Instruction::SkipKeyN { x } => xnn(0xe, x, 0xa1),
+            Instruction::LongI => 0xf000,
+            Instruction::Plane { x } => xnn(0xf, x, 0x01),
+            Instruction::Audio => 0xf002,
+            Instruction::GetDelay { x } => xnn(0xf, x, 0x07),
+            Instruction::Key { x } => xnn(0xf, x, 0x0a),
+            Instruction::SetDelay { x } => xnn(0xf, x, 0x15),
+            Instruction::SetSound { x } => xnn(0xf, x, 0x18),
+            Instruction::AdiI { x } => xnn(0xf, x, 0x1e),
+            Instruction::SpriteChar { x } => xnn(0xf, x, 0x29),
+            Instruction::BigSpriteChar { x } => xnn(0xf, x, 0x30),
+            Instruction::MovBcd { x } => xnn(0xf, x, 0x33),
+            Instruction::Pitch { x } => xnn(0xf, x, 0x3a),
+            Instruction::Store { x } => xnn(0xf, x, 0x55),
+            Instruction::Load { x } => xnn(0xf, x, 0x65),
+            Instruction::StoreFlags { x } => xnn(0xf, x, 0x75),
+            Instruction::LoadFlags { x } => xnn(0xf, x, 0x85),
+        }
+    }
+
+    /* size in bytes, including any operand words that follow the opcode */
+    pub fn size(&self) -> usize {
+        match *self {
+            Instruction::LongI => 4,
+            _ => 2,
+        }
+    }
+
+    /* the first machine that had this instruction */
+    pub fn machine(&self) -> Machine {
+        match *self {
+            Instruction::ScrollUp { .. }
+            | Instruction::StoreRange { .. }
+            | Instruction::LoadRange { .. }
+            | Instruction::LongI
+            | Instruction::Plane { .. }
+            | Instruction::Audio
+            | Instruction::Pitch { .. } => Machine::XoChip,
+            Instruction::ScrollDown { .. }
+            | Instruction::ScrollRight
+            | Instruction::ScrollLeft
+            | Instruction::Exit
+            | Instruction::Lores
+            | Instruction::Hires
+            | Instruction::BigSpriteChar { .. }
+            | Instruction::StoreFlags { .. }
+            | Instruction::LoadFlags { .. } => Machine::SuperChip,
+            _ => Machine::Chip8,
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::Instruction;
+
+    #[test]
+    fn decode_encode_round_trip() {
+        for opcode in 0..=0xffffu16 {
+            if let Some(instr) = Instruction::decode(opcode) {
+                assert_eq!(instr.encode(), opcode,
+                           "{:04X} decoded as {:?}", opcode, instr);
+            }
+        }
+    }
+
+    #[test]
+    fn decoded_opcode_count() {
+        /* 0000, 00E0, 00EE, 00FB-00FF, F000, F002
+         * + 1NNN-4XNN, 6XNN, 7XNN, ANNN-DXYN: 10 groups of 4096
+         * + 5XY0, 5XY2, 5XY3, 8XY0-7, 8XYE, 9XY0: 13 groups of 256
+         * + 00CN, 00DN, EX9E, EXA1, 14 FXNN: 18 groups of 16 */
+        let decoded = (0..=0xffffu16)
+                        .filter(|&op| Instruction::decode(op).is_some())
+                        .count();
+
+        assert_eq!(decoded, 10 + 10 * 4096 + 13 * 256 + 18 * 16);
+    }
+
+    #[test]
+    fn known_opcodes() {
+        assert_eq!(Instruction::decode(0x8125),
+                   Some(Instruction::Sub { x: 1, y: 2 }));
+        assert_eq!(Instruction::decode(0x812e),
+                   Some(Instruction::Shl { x: 1, y: 2 }));
+        assert_eq!(Instruction::decode(0xd12f),
+                   Some(Instruction::Sprite { x: 1, y: 2, n: 0xf }));
+        assert_eq!(Instruction::decode(0x00c5),
+                   Some(Instruction::ScrollDown { n: 5 }));
+        assert_eq!(Instruction::decode(0x0123), None);
+        assert_eq!(Instruction::decode(0x5121), None);
+        assert_eq!(Instruction::decode(0xf1ff), None);
+    }
+}
@@ -0,0 +1,48 @@
+/* the 16-key hex keypad. keys are addressed by their CHIP-8 value 0x0-0xF,
+ * mapping host input onto them is up to the frontend */
+pub struct Keypad {
+    keys: [bool; 16]
+}
+
+impl Default for Keypad {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Keypad {
+    pub fn new() -> Keypad {
+        Keypad { keys: [false; 16] }
+    }
+
+    pub fn pressed(&self, index: usize) -> bool {
+        self.keys[index]
+    }
+
+    pub fn press(&mut self, index: usize) {
+        self.keys[index] = true;
+    }
+
+    pub fn release(&mut self, index: usize) {
+        self.keys[index] = false;
+    }
+
+    /* one bit per key, key 0 in bit 0 */
+    pub fn bits(&self) -> u16 {
+        self.keys
+            .iter()
+            .enumerate()
+            .fold(0, |bits, (key, &down)| bits | (down as u16) << key)
+    }
+
+    pub fn set_bits(&mut self, bits: u16) {
+        for (key, down) in self.keys.iter_mut().enumerate() {
+            *down = bits & (1 << key) != 0;
+        }
+    }
+
+    /* the lowest numbered key that is down, if any */
+    pub fn first_pressed(&self) -> Option<usize> {
+        self.keys.iter().position(|&down| down)
+    }
+}
@@ -0,0 +1,36 @@
+/* CHIP-8 interpreter core. this crate has no frontend dependencies so it can
+ * be embedded in the SDL emulator, headless runners or anything else that can
+ * feed it key presses and draw the framebuffer it hands back */
+
+pub mod consts;
+
+mod cpu;
+mod error;
+mod font;
+mod hash;
+mod instruction;
+mod keypad;
+mod movie;
+mod pbm;
+mod platform;
+mod quirks;
+mod rewind;
+mod rng;
+
+pub use cpu::Cpu;
+pub use cpu::OutputState;
+pub use error::CpuError;
+pub use error::MovieError;
+pub use error::StateError;
+pub use hash::rom_hash;
+pub use instruction::Instruction;
+pub use keypad::Keypad;
+pub use movie::Movie;
+pub use pbm::pbm;
+pub use platform::Machine;
+pub use platform::Platform;
+pub use platform::PLATFORMS;
+pub use quirks::Quirks;
+pub use rewind::Rewind;
+pub use rng::RandomAlgorithm;
+pub use rng::Rng;
@@ -0,0 +1,219 @@
+use crate::cpu::Cpu;
+use crate::error::MovieError;
+use crate::quirks::Quirks;
+
+
+/* a movie file, little endian:
+ *
+ *   "CH8M" version:u16 rom_hash:u64 seed:u64 quirks:u8
+ *   start state:(len:u32 bytes) frames:(count:u32 keypad:u16...)
+ *
+ * the seed and quirks are in the start state too, they're repeated in the
+ * header so they can be read without loading it */
+const MAGIC: &[u8; 4] = b"CH8M";
+const VERSION: u16 = 1;
+
+/* the keypad each frame from a save state on. the emulator is deterministic,
+ * so playing the same keys from the same state gives the same run */
+pub struct Movie {
+    pub rom_hash: u64,
+    /* what the RNG was seeded with when the recording began */
+    pub seed: u64,
+    pub quirks: Quirks,
+    /* Cpu::save_state() from when the recording began */
+    pub start: Vec<u8>,
+    /* Keypad::bits() as each frame ran */
+    pub frames: Vec<u16>,
+}
+
+impl Movie {
+    /* start recording from the CPU as it is now */
+    pub fn record(cpu: &Cpu, seed: u64) -> Movie {
+        Movie {
+            rom_hash: cpu.rom_hash(),
+            seed,
+            quirks: cpu.quirks(),
+            start: cpu.save_state(),
+            frames: Vec::new(),
+        }
+    }
+
+    /* call before each Cpu::run_frame() while recording */
+    pub fn record_frame(&mut self, cpu: &Cpu) {
+        self.frames.push(cpu.keypad.bits());
+    }
+
+    /* put the CPU in the movie's start state, ready for play_frame() */
+    pub fn start(&self, cpu: &mut Cpu) -> Result<(), MovieError> {
+        cpu.load_state(&self.start)?;
+        Ok(())
+    }
+
+    /* set the keypad for a frame of playback, before Cpu::run_frame().
+     * false once the movie has run out */
+    pub fn play_frame(&self, cpu: &mut Cpu, frame: usize) -> bool {
+        match self.frames.get(frame) {
+            Some(&bits) => {
+                cpu.keypad.set_bits(bits);
+                true
+            },
+            None => false,
+        }
+    }
+
+    /* play the whole movie without a frontend */
+    pub fn replay(&self, cpu: &mut Cpu) -> Result<(), MovieError> {
+        self.start(cpu)?;
+
+        for frame in 0..self.frames.len() {
+            self.play_frame(cpu, frame);
+            cpu.run_frame()?;
+        }
+
+        Ok(())
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.start.len() + self.frames.len() * 2 + 64);
+
+        out.extend_from_slice(MAGIC);
+        out.extend_from_slice(&VERSION.to_le_bytes());
+        out.extend_from_slice(&self.rom_hash.to_le_bytes());
+        out.extend_from_slice(&self.seed.to_le_bytes());
+        out.push(self.quirks.bits());
+        out.extend_from_slice(&(self.start.len() as u32).to_le_bytes());
+        out.extend_from_slice(&self.start);
+        out.extend_from_slice(&(self.frames.len() as u32).to_le_bytes());
+        for bits in &self.frames {
+            out.extend_from_slice(&bits.to_le_bytes());
+        }
+
+        out
+    }
+
+    pub fn from_bytes(data: &[u8]) -> Result<Movie, MovieError> {
+        let mut rest = data;
+        let mut take = |len: usize| -> Result<&[u8], MovieError> {
+            if rest.len() < len {
+                return Err(MovieError::Truncated);
+            }
+            let (bytes, tail) = rest.split_at(len);
+            rest = tail;
+            Ok(bytes)
+        };
+
+        if take(MAGIC.len()).ok() != Some(&MAGIC[..]) {
+            return Err(MovieError::NotAMovie);
+        }
+
+        let version = u16::from_le_bytes(take(2)?.try_into().unwrap());
+        if version > VERSION {
+            return Err(MovieError::UnsupportedVersion(version));
+        }
+
+        let rom_hash = u64::from_le_bytes(take(8)?.try_into().unwrap());
+        let seed = u64::from_le_bytes(take(8)?.try_into().unwrap());
+        let quirks = Quirks::from_bits(take(1)?[0]);
+
+        let start_len = u32::from_le_bytes(take(4)?.try_into().unwrap()) as usize;
+        let start = take(start_len)?.to_vec();
+
+        let count = u32::from_le_bytes(take(4)?.try_into().unwrap()) as usize;
+        let frames = take(count.checked_mul(2).ok_or(MovieError::Truncated)?)?
+                        .chunks_exact(2)
+                        .map(|bits| u16::from_le_bytes([bits[0], bits[1]]))
+                        .collect();
+
+        Ok(Movie { rom_hash, seed, quirks, start, frames })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::StateError;
+    use crate::hash::rom_hash;
+    use crate::rng::RandomAlgorithm;
+    use crate::rng::Rng;
+
+    const PONG: &[u8] = include_bytes!("../../roms/pong.ch8");
+
+    /* pong a second in, recorded for a few seconds of player 1 going up
+     * and down. returns the movie and the CPU as the live run left it */
+    fn recorded() -> (Movie, Cpu) {
+        let mut cpu = Cpu::default();
+        cpu.set_rng(Rng::new(RandomAlgorithm::SplitMix, 1234));
+        cpu.load_rom_bytes(PONG);
+        for _ in 0..60 {
+            cpu.run_frame().unwrap();
+        }
+
+        let mut movie = Movie::record(&cpu, 1234);
+        for frame in 0..240 {
+            cpu.keypad.set_bits(match frame / 40 % 3 {
+                0 => 1 << 0x1,
+                1 => 1 << 0x4,
+                _ => 0,
+            });
+            movie.record_frame(&cpu);
+            cpu.run_frame().unwrap();
+        }
+
+        (movie, cpu)
+    }
+
+    #[test]
+    fn replay_ends_where_the_live_run_did() {
+        let (movie, live) = recorded();
+
+        /* seeded differently and never run, the start state puts that right */
+        let mut cpu = Cpu::default();
+        cpu.set_rng(Rng::new(RandomAlgorithm::SplitMix, 99));
+        cpu.load_rom_bytes(PONG);
+        movie.replay(&mut cpu).unwrap();
+
+        assert_eq!(cpu.output().vram, live.output().vram);
+        assert_eq!(cpu.save_state(), live.save_state());
+    }
+
+    #[test]
+    fn bytes_round_trip() {
+        let (movie, _) = recorded();
+        let read = Movie::from_bytes(&movie.to_bytes()).unwrap();
+
+        assert_eq!(read.rom_hash, rom_hash(PONG));
+        assert_eq!(read.seed, 1234);
+        assert_eq!(read.quirks, movie.quirks);
+        assert_eq!(read.start, movie.start);
+        assert_eq!(read.frames, movie.frames);
+        assert_eq!(read.to_bytes(), movie.to_bytes());
+    }
+
+    #[test]
+    fn bad_files_are_refused() {
+        let (movie, _) = recorded();
+        let bytes = movie.to_bytes();
+        let error = |data: &[u8]| Movie::from_bytes(data).err().unwrap();
+
+        assert_eq!(error(b"CH8S"), MovieError::NotAMovie);
+
+        let mut newer = bytes.clone();
+        newer[4..6].copy_from_slice(&(VERSION + 1).to_le_bytes());
+        assert_eq!(error(&newer), MovieError::UnsupportedVersion(VERSION + 1));
+
+        /* in the header, the start state and the frames */
+        assert_eq!(error(&bytes[..12]), MovieError::Truncated);
+        assert_eq!(error(&bytes[..100]), MovieError::Truncated);
+        assert_eq!(error(&bytes[..bytes.len() - 1]), MovieError::Truncated);
+    }
+
+    #[test]
+    fn movies_only_play_on_their_rom() {
+        let (movie, _) = recorded();
+
+        let mut cpu = Cpu::default();
+        cpu.load_rom_bytes(&PONG[1..]);
+        let error = movie.replay(&mut cpu).err().unwrap();
+        assert!(matches!(error, MovieError::State(StateError::WrongRom { .. })), "{:?}", error);
+    }
+}
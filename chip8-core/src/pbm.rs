@@ -0,0 +1,15 @@
+/* the framebuffer as a plain (ascii) PBM, the simplest image format there
+ * is, for screenshots and golden images. pixels are the bitplane values
+ * OutputState::vram holds and any plane counts as lit. 1 is black in PBM,
+ * so lit pixels are 0 to come out white on black like the screen */
+pub fn pbm(pixels: &[u8], width: usize, height: usize) -> String {
+    let mut out = format!("P1\n{} {}\n", width, height);
+
+    for row in pixels.chunks_exact(width).take(height) {
+        let line: Vec<&str> = row.iter().map(|&p| if p != 0 { "0" } else { "1" }).collect();
+        out.push_str(&line.join(" "));
+        out.push('\n');
+    }
+
+    out
+}
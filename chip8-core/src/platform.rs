@@ -0,0 +1,162 @@
+use std::str::FromStr;
+
+use crate::consts::HEIGHT;
+use crate::consts::RAM_SIZE;
+use crate::consts::WIDTH;
+use crate::font;
+use crate::instruction::Instruction;
+use crate::quirks::Quirks;
+
+
+/* which instruction set a platform understands */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Machine {
+    Chip8,
+    SuperChip,
+    XoChip,
+}
+
+/* everything that differs between the machines CHIP-8 programs were written
+ * for. the CPU is built from one of these, and the frontend and disassembler
+ * read it to agree with the CPU about what they are looking at */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Platform {
+    pub name: &'static str,
+    pub machine: Machine,
+    /* bytes of addressable memory */
+    pub ram_size: usize,
+    /* number of return addresses the stack holds */
+    pub stack_depth: usize,
+    /* the largest display mode. every platform boots into the 64x32 one */
+    pub width: usize,
+    pub height: usize,
+    /* instructions executed per 60Hz frame */
+    pub instructions_per_frame: u32,
+    pub quirks: Quirks,
+    pub font: &'static [u8],
+    /* the FX30 font, empty if the machine doesn't have one */
+    pub big_font: &'static [u8],
+}
+
+/* the original interpreter on the RCA COSMAC VIP, 1977 */
+pub const VIP: Platform = Platform {
+    name: "vip",
+    machine: Machine::Chip8,
+    ram_size: RAM_SIZE,
+    stack_depth: 12,
+    width: WIDTH,
+    height: HEIGHT,
+    instructions_per_frame: 15,
+    quirks: Quirks {
+        shift_uses_vy: true,
+        load_store_increments_i: true,
+        jump_with_vx: false,
+        vf_reset: true,
+        clip_sprites: true,
+        display_wait: true,
+        index_overflow: false,
+    },
+    font: &font::VIP_FONT,
+    big_font: &[],
+};
+
+/* CHIP-48 on the HP-48 calculators, 1990 */
+pub const CHIP48: Platform = Platform {
+    name: "chip48",
+    machine: Machine::Chip8,
+    ram_size: RAM_SIZE,
+    stack_depth: 16,
+    width: WIDTH,
+    height: HEIGHT,
+    instructions_per_frame: 30,
+    quirks: Quirks {
+        shift_uses_vy: false,
+        load_store_increments_i: false,
+        jump_with_vx: true,
+        vf_reset: false,
+        clip_sprites: true,
+        display_wait: false,
+        index_overflow: false,
+    },
+    font: &font::FONT,
+    big_font: &[],
+};
+
+/* SUPER-CHIP 1.1 on the HP-48, 1991 */
+pub const SCHIP: Platform = Platform {
+    name: "schip",
+    machine: Machine::SuperChip,
+    ram_size: RAM_SIZE,
+    stack_depth: 16,
+    width: 128,
+    height: 64,
+    instructions_per_frame: 30,
+    quirks: Quirks {
+        shift_uses_vy: false,
+        load_store_increments_i: false,
+        jump_with_vx: true,
+        vf_reset: false,
+        clip_sprites: true,
+        display_wait: false,
+        index_overflow: false,
+    },
+    font: &font::FONT,
+    big_font: &font::SCHIP_BIG_FONT,
+};
+
+/* XO-CHIP, as defined by Octo, 2014 */
+pub const XOCHIP: Platform = Platform {
+    name: "xochip",
+    machine: Machine::XoChip,
+    ram_size: 0x10000,
+    stack_depth: 16,
+    width: 128,
+    height: 64,
+    instructions_per_frame: 1000,
+    quirks: Quirks {
+        shift_uses_vy: true,
+        load_store_increments_i: true,
+        jump_with_vx: false,
+        vf_reset: false,
+        clip_sprites: false,
+        display_wait: false,
+        index_overflow: false,
+    },
+    font: &font::FONT,
+    big_font: &font::XO_BIG_FONT,
+};
+
+pub const PLATFORMS: [Platform; 4] = [VIP, CHIP48, SCHIP, XOCHIP];
+
+impl Default for Platform {
+    fn default() -> Self {
+        VIP
+    }
+}
+
+impl Platform {
+    /* the largest ROM that fits between 0x200 and the end of memory */
+    pub fn max_rom_size(&self) -> usize {
+        self.ram_size - 0x200
+    }
+
+    /* whether this machine understands the instruction */
+    pub fn supports(&self, instr: &Instruction) -> bool {
+        instr.machine() <= self.machine
+    }
+}
+
+impl FromStr for Platform {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        PLATFORMS.iter()
+            .find(|p| p.name == s)
+            .copied()
+            .ok_or_else(|| {
+                let names: Vec<&str> = PLATFORMS.iter().map(|p| p.name).collect();
+                format!("unknown platform '{}', expected one of: {}",
+                        s, names.join(", "))
+            })
+    }
+}
@@ -0,0 +1,117 @@
+use std::fmt;
+use std::str::FromStr;
+
+
+/* the original interpreters disagree on a handful of opcodes and ROMs are
+ * written against one of them. Quirks::default() has every switch off, but
+ * a CPU takes its platform's set: the default platform, vip, turns on all of
+ * them but jump and overflow. this emulator used to behave as if only
+ * overflow was on */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Quirks {
+    /* 8XY6/8XYE shift VY into VX instead of shifting VX in place */
+    pub shift_uses_vy: bool,
+    /* FX55/FX65 leave I pointing after the last register stored/loaded */
+    pub load_store_increments_i: bool,
+    /* BNNN jumps to XNN plus VX instead of NNN plus V0 */
+    pub jump_with_vx: bool,
+    /* 8XY1/8XY2/8XY3 reset VF to 0 */
+    pub vf_reset: bool,
+    /* DXYN clips sprites at the screen edges instead of wrapping them */
+    pub clip_sprites: bool,
+    /* DXYN waits for the next vertical blank before execution continues */
+    pub display_wait: bool,
+    /* FX1E sets VF to 1 if I ends up past 0xF00 and to 0 otherwise. none of
+     * the platforms do, but it's how this emulator used to behave */
+    pub index_overflow: bool,
+}
+
+/* names used on the command line, in the same order as the fields */
+const NAMES: [&str; 7] = ["shift", "memory", "jump", "vfreset", "clip", "vblank", "overflow"];
+
+impl Quirks {
+    /* one bit per quirk, in field order from bit 0, for save states */
+    pub fn bits(&self) -> u8 {
+        self.flags()
+            .iter()
+            .enumerate()
+            .fold(0, |bits, (bit, &on)| bits | (on as u8) << bit)
+    }
+
+    pub fn from_bits(bits: u8) -> Quirks {
+        let mut quirks = Quirks::default();
+
+        for (bit, name) in NAMES.iter().enumerate() {
+            if let Some(flag) = quirks.flag_mut(name) {
+                *flag = bits & (1 << bit) != 0;
+            }
+        }
+
+        quirks
+    }
+
+    fn flags(&self) -> [bool; 7] {
+        [
+            self.shift_uses_vy,
+            self.load_store_increments_i,
+            self.jump_with_vx,
+            self.vf_reset,
+            self.clip_sprites,
+            self.display_wait,
+            self.index_overflow,
+        ]
+    }
+
+    fn flag_mut(&mut self, name: &str) -> Option<&mut bool> {
+        match name {
+            "shift" => Some(&mut self.shift_uses_vy),
+            "memory" => Some(&mut self.load_store_increments_i),
+            "jump" => Some(&mut self.jump_with_vx),
+            "vfreset" => Some(&mut self.vf_reset),
+            "clip" => Some(&mut self.clip_sprites),
+            "vblank" => Some(&mut self.display_wait),
+            "overflow" => Some(&mut self.index_overflow),
+            _ => None,
+        }
+    }
+}
+
+/* parse a comma separated list of the quirks to turn on, e.g.
+ * "shift,memory,clip". "none" turns them all off */
+impl FromStr for Quirks {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut quirks = Quirks::default();
+
+        for name in s.split(',').map(str::trim) {
+            if name == "none" || name.is_empty() {
+                continue;
+            }
+
+            match quirks.flag_mut(name) {
+                Some(flag) => *flag = true,
+                None => return Err(format!("unknown quirk '{}', expected one of: {}",
+                                           name, NAMES.join(", "))),
+            }
+        }
+
+        Ok(quirks)
+    }
+}
+
+impl fmt::Display for Quirks {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let on: Vec<&str> = NAMES.iter()
+                                .zip(self.flags())
+                                .filter(|(_, on)| *on)
+                                .map(|(name, _)| *name)
+                                .collect();
+
+        if on.is_empty() {
+            write!(f, "none")
+        } else {
+            write!(f, "{}", on.join(","))
+        }
+    }
+}
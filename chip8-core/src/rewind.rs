@@ -0,0 +1,178 @@
+use std::collections::VecDeque;
+
+use crate::cpu::Cpu;
+use crate::error::StateError;
+
+
+/* the last so many frames of save states, to step back through one at a
+ * time. only the newest is kept whole. every older one is stored as the XOR
+ * of it with the state after it, run length encoded: a frame changes a few
+ * registers and the odd sprite, so that's mostly one long run of zeros */
+pub struct Rewind {
+    capacity: usize,
+    latest: Option<Vec<u8>>,
+    /* oldest first */
+    deltas: VecDeque<Vec<u8>>,
+}
+
+impl Rewind {
+    /* keep up to capacity frames to go back through */
+    pub fn new(capacity: usize) -> Rewind {
+        Rewind { capacity, latest: None, deltas: VecDeque::new() }
+    }
+
+    /* how many frames back it can go */
+    pub fn len(&self) -> usize {
+        self.deltas.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.deltas.is_empty()
+    }
+
+    /* the bytes held, for keeping an eye on the memory it takes */
+    pub fn size(&self) -> usize {
+        self.latest.as_ref().map_or(0, Vec::len) + self.deltas.iter().map(Vec::len).sum::<usize>()
+    }
+
+    /* record the state at the end of a frame */
+    pub fn push(&mut self, cpu: &Cpu) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let state = cpu.save_state();
+
+        if let Some(latest) = self.latest.take() {
+            self.deltas.push_back(encode(&latest, &state));
+
+            if self.deltas.len() > self.capacity {
+                self.deltas.pop_front();
+            }
+        }
+
+        self.latest = Some(state);
+    }
+
+    /* go back a frame: drop the newest state and load the one before it,
+     * which becomes the newest. false once there's nothing left to go back
+     * to */
+    pub fn pop(&mut self, cpu: &mut Cpu) -> Result<bool, StateError> {
+        let (Some(latest), Some(delta)) = (self.latest.as_ref(), self.deltas.pop_back()) else {
+            return Ok(false);
+        };
+
+        let previous = decode(latest, &delta);
+        cpu.load_state(&previous)?;
+        self.latest = Some(previous);
+
+        Ok(true)
+    }
+
+    pub fn clear(&mut self) {
+        self.latest = None;
+        self.deltas.clear();
+    }
+}
+
+/* what it takes to get back from new to old: old's length, then runs of
+ * (unchanged bytes, changed bytes, the changed bytes XOR new's) */
+fn encode(old: &[u8], new: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    write_varint(&mut out, old.len());
+
+    let xor = |i: usize| old.get(i).copied().unwrap_or(0) ^ new.get(i).copied().unwrap_or(0);
+    let len = old.len().max(new.len());
+    let mut i = 0;
+
+    while i < len {
+        let start = i;
+        while i < len && xor(i) == 0 {
+            i += 1;
+        }
+        write_varint(&mut out, i - start);
+
+        let start = i;
+        while i < len && xor(i) != 0 {
+            i += 1;
+        }
+        write_varint(&mut out, i - start);
+        out.extend((start..i).map(xor));
+    }
+
+    out
+}
+
+/* rebuild old from new and encode(old, new) */
+fn decode(new: &[u8], delta: &[u8]) -> Vec<u8> {
+    let mut pos = 0;
+    let old_len = read_varint(delta, &mut pos);
+    let mut old = new.to_vec();
+    old.resize(new.len().max(old_len), 0);
+
+    let mut i = 0;
+    while pos < delta.len() {
+        i += read_varint(delta, &mut pos);
+
+        let changed = read_varint(delta, &mut pos);
+        for (byte, &x) in old[i..i + changed].iter_mut().zip(&delta[pos..pos + changed]) {
+            *byte ^= x;
+        }
+        i += changed;
+        pos += changed;
+    }
+
+    old.truncate(old_len);
+    old
+}
+
+/* LEB128: 7 bits at a time, low first, top bit set if more follow */
+fn write_varint(out: &mut Vec<u8>, mut value: usize) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn read_varint(data: &[u8], pos: &mut usize) -> usize {
+    let mut value = 0;
+    let mut shift = 0;
+
+    loop {
+        let byte = data[*pos];
+        *pos += 1;
+        value |= ((byte & 0x7f) as usize) << shift;
+        shift += 7;
+
+        if byte & 0x80 == 0 {
+            return value;
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delta_round_trip() {
+        let a: Vec<u8> = (0..1000).map(|i| (i % 251) as u8).collect();
+        let mut b = a.clone();
+        b[3] ^= 0xff;
+        b[500..520].fill(7);
+        let shorter = a[..300].to_vec();
+        let longer: Vec<u8> = a.iter().chain(&[1, 2, 3]).copied().collect();
+
+        for (old, new) in [(&a, &b), (&b, &a), (&a, &shorter), (&shorter, &a), (&a, &longer), (&a, &a)] {
+            assert_eq!(&decode(new, &encode(old, new)), old);
+        }
+    }
+
+    #[test]
+    fn unchanged_state_is_small() {
+        let a = vec![0x55; 4096];
+        assert!(encode(&a, &a).len() < 8);
+    }
+}
@@ -0,0 +1,45 @@
+/* how CXNN comes up with its random numbers. there's only the one for now,
+ * but save states record it so more can be added without breaking them */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RandomAlgorithm {
+    /* splitmix64, good quality and cheap */
+    #[default]
+    SplitMix,
+}
+
+/* a seedable generator, so a run can be repeated exactly. all of its state
+ * is the algorithm and one word, which is what a save state records */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rng {
+    algorithm: RandomAlgorithm,
+    state: u64,
+}
+
+impl Rng {
+    /* the seed is the whole state, so passing another generator's state()
+     * picks up exactly where it left off */
+    pub fn new(algorithm: RandomAlgorithm, seed: u64) -> Rng {
+        Rng { algorithm, state: seed }
+    }
+
+    pub fn algorithm(&self) -> RandomAlgorithm {
+        self.algorithm
+    }
+
+    pub fn state(&self) -> u64 {
+        self.state
+    }
+
+    /* the next random byte */
+    pub fn next_byte(&mut self) -> u8 {
+        match self.algorithm {
+            RandomAlgorithm::SplitMix => {
+                self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+                let mut z = self.state;
+                z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+                z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+                (z ^ (z >> 31)) as u8
+            },
+        }
+    }
+}
@@ -0,0 +1,148 @@
+/* golden image tests: boot each ROM in roms/ with a fixed seed and scripted
+ * keys, run it for a while and compare the screen with the image checked in
+ * under tests/golden. run with CHIP8_BLESS=1 to write new goldens after an
+ * intended change. a failure writes a diff image to the target directory,
+ * red where only the golden has a pixel and green where only the run does */
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use chip8_core::pbm;
+use chip8_core::Cpu;
+use chip8_core::RandomAlgorithm;
+use chip8_core::Rng;
+
+
+const SEED: u64 = 0x5eed;
+
+/* keys held from a frame on, as Keypad::bits(), until the next entry */
+type Script<'a> = &'a [(u64, u16)];
+
+const fn key(index: u16) -> u16 {
+    1 << index
+}
+
+/* press and release each key in turn, ten frames apart, for ROMs that read
+ * keys with FX0A */
+fn taps(start: u64, keys: &[u16]) -> Vec<(u64, u16)> {
+    keys.iter()
+        .enumerate()
+        .flat_map(|(n, &index)| {
+            let frame = start + n as u64 * 10;
+            [(frame, key(index)), (frame + 5, 0)]
+        })
+        .collect()
+}
+
+fn golden(rom: &str, frames: u64, script: Script<'_>) {
+    let root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let data = fs::read(root.join("../roms").join(format!("{}.ch8", rom))).unwrap();
+
+    let mut cpu = Cpu::default();
+    cpu.set_rng(Rng::new(RandomAlgorithm::SplitMix, SEED));
+    cpu.load_rom_bytes(&data);
+
+    for frame in 0..frames {
+        if let Some(&(_, bits)) = script.iter().rev().find(|&&(start, _)| start <= frame) {
+            cpu.keypad.set_bits(bits);
+        }
+
+        if let Err(err) = cpu.run_frame() {
+            panic!("{} crashed on frame {}: {}", rom, frame, err);
+        }
+    }
+
+    let output = cpu.output();
+    let actual = pbm(output.vram, output.width, output.height);
+    let path = root.join("tests/golden").join(format!("{}.pbm", rom));
+
+    if env::var_os("CHIP8_BLESS").is_some() {
+        fs::write(&path, &actual).unwrap();
+        return;
+    }
+
+    let expected = fs::read_to_string(&path)
+                    .unwrap_or_else(|_| panic!("no golden {}, run with CHIP8_BLESS=1", path.display()));
+
+    if expected != actual {
+        let diff_path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(format!("{}.diff.ppm", rom));
+        fs::write(&diff_path, diff(&expected, &actual)).unwrap();
+        panic!("{} doesn't match {}, diff in {}", rom, path.display(), diff_path.display());
+    }
+}
+
+/* plain PPM of two PBMs: white where both are lit, red where only the
+ * expected one is and green where only the actual one is */
+fn diff(expected: &str, actual: &str) -> String {
+    let (expected, actual) = (parse_pbm(expected), parse_pbm(actual));
+    let width = expected.0.max(actual.0);
+    let height = expected.1.max(actual.1);
+
+    let mut out = format!("P3\n{} {}\n255\n", width, height);
+    for y in 0..height {
+        for x in 0..width {
+            let color = match (lit(&expected, x, y), lit(&actual, x, y)) {
+                (true, true) => "255 255 255",
+                (true, false) => "255 0 0",
+                (false, true) => "0 255 0",
+                (false, false) => "0 0 0",
+            };
+            out.push_str(color);
+            out.push('\n');
+        }
+    }
+
+    out
+}
+
+/* width, height and which pixels are lit */
+fn parse_pbm(pbm: &str) -> (usize, usize, Vec<bool>) {
+    let mut words = pbm.split_whitespace().skip(1);
+    let width = words.next().and_then(|w| w.parse().ok()).unwrap_or(0);
+    let height = words.next().and_then(|h| h.parse().ok()).unwrap_or(0);
+    (width, height, words.map(|word| word == "0").collect())
+}
+
+fn lit(image: &(usize, usize, Vec<bool>), x: usize, y: usize) -> bool {
+    let (width, height, pixels) = image;
+    x < *width && y < *height && pixels.get(y * width + x).copied().unwrap_or(false)
+}
+
+#[test]
+fn chip8_logo() {
+    golden("chip8-logo", 60, &[]);
+}
+
+#[test]
+fn fishie() {
+    golden("fishie", 60, &[]);
+}
+
+#[test]
+fn life() {
+    /* a glider, cells as Y then X, then F to start and 0 for 255 scans */
+    let script = taps(10, &[0x1, 0x2, 0x2, 0x3, 0x3, 0x1, 0x3, 0x2, 0x3, 0x3, 0xf, 0x0]);
+    golden("life", 600, &script);
+}
+
+#[test]
+fn pong() {
+    /* left paddle up, then down */
+    golden("pong", 600, &[(0, 0), (100, key(0x1)), (140, 0), (300, key(0x4)), (380, 0)]);
+}
+
+#[test]
+fn space_invaders() {
+    /* start, walk left firing, then right */
+    golden("space-invaders", 900, &[(0, 0), (120, key(0x5)), (130, 0),
+                                    (300, key(0x4) | key(0x5)), (360, 0),
+                                    (500, key(0x6)), (560, key(0x5)), (570, 0)]);
+}
+
+#[test]
+fn tetris() {
+    /* move and rotate the first few pieces */
+    golden("tetris", 900, &[(0, 0), (60, key(0x5)), (70, 0), (100, key(0x6)), (130, 0),
+                            (300, key(0x4)), (330, 0), (500, key(0x7)), (540, 0)]);
+}
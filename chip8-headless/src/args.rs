@@ -0,0 +1,135 @@
+use std::env;
+
+use chip8_core::Platform;
+use chip8_core::Quirks;
+
+
+pub const USAGE: &str = "\
+usage: chip8-headless [options] <rom>
+
+runs a ROM without a display until one of:
+    - the number of frames given with --frames, or the end of the movie
+    - pc reaches the address given with --until-pc
+    - a jump to itself, which is how most test ROMs end
+    - 00FD, or an error
+
+options:
+    --platform <name>   machine to emulate: vip (default), chip48, schip,
+                        xochip
+    --quirks <list>     comma separated quirks to enable, or \"none\"
+    --ipf <n>           instructions per 60Hz frame
+    --seed <n>          seed for the random numbers, default 0
+    --frames <n>        frames to run for, default 600
+    --until-pc <addr>   stop when pc reaches this hex address
+    --input <file>      scripted keypad input, lines of a frame number and
+                        the hex keys held from then on
+    --movie <file>      play back a movie recorded by the emulator instead
+    --screenshot <file> write the final screen to a .png or .pbm file
+    --dump <file>       write the registers as JSON, - for stdout";
+
+/* command line options */
+pub struct Args {
+    pub rom: String,
+    pub platform: Platform,
+    pub quirks: Option<Quirks>,
+    pub instructions_per_frame: Option<u32>,
+    pub seed: u64,
+    /* none means 600, or the length of the movie */
+    pub frames: Option<u64>,
+    pub until_pc: Option<usize>,
+    pub input: Option<String>,
+    pub movie: Option<String>,
+    pub screenshot: Option<String>,
+    pub dump: Option<String>,
+}
+
+impl Args {
+    pub fn parse() -> Result<Args, String> {
+        let mut rom = None;
+        let mut platform = Platform::default();
+        let mut quirks = None;
+        let mut instructions_per_frame = None;
+        let mut seed = 0;
+        let mut frames = None;
+        let mut until_pc = None;
+        let mut input = None;
+        let mut movie = None;
+        let mut screenshot = None;
+        let mut dump = None;
+
+        let mut args = env::args().skip(1);
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--platform" => {
+                    platform = value(&mut args, &arg)?.parse()?;
+                },
+                "--quirks" => {
+                    quirks = Some(value(&mut args, &arg)?.parse()?);
+                },
+                "--ipf" => {
+                    instructions_per_frame = Some(number(&value(&mut args, &arg)?, &arg)
+                                                    .ok()
+                                                    .filter(|&ipf: &u32| ipf > 0)
+                                                    .ok_or("--ipf expects a number above 0")?);
+                },
+                "--seed" => {
+                    seed = number(&value(&mut args, &arg)?, &arg)?;
+                },
+                "--frames" => {
+                    frames = Some(number(&value(&mut args, &arg)?, &arg)?);
+                },
+                "--until-pc" => {
+                    let addr = value(&mut args, &arg)?;
+                    let hex = addr.strip_prefix("0x").unwrap_or(&addr);
+                    until_pc = Some(usize::from_str_radix(hex, 16)
+                                .map_err(|_| format!("{} expects a hex address, got '{}'", arg, addr))?);
+                },
+                "--input" => {
+                    input = Some(value(&mut args, &arg)?);
+                },
+                "--movie" => {
+                    movie = Some(value(&mut args, &arg)?);
+                },
+                "--screenshot" => {
+                    screenshot = Some(value(&mut args, &arg)?);
+                },
+                "--dump" => {
+                    dump = Some(value(&mut args, &arg)?);
+                },
+                _ if arg.starts_with("--") => {
+                    return Err(format!("unknown option '{}'", arg));
+                },
+                _ => rom = Some(arg),
+            }
+        }
+
+        if input.is_some() && movie.is_some() {
+            return Err("--input and --movie can't be used together".to_string());
+        }
+
+        Ok(Args {
+            rom: rom.ok_or("no rom given")?,
+            platform,
+            quirks,
+            instructions_per_frame,
+            seed,
+            frames,
+            until_pc,
+            input,
+            movie,
+            screenshot,
+            dump,
+        })
+    }
+}
+
+/* the value following an option */
+fn value(args: &mut impl Iterator<Item = String>, opt: &str) -> Result<String, String> {
+    args.next().ok_or_else(|| format!("{} needs a value", opt))
+}
+
+/* a numeric option value */
+fn number<T: std::str::FromStr>(value: &str, opt: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("{} expects a number, got '{}'", opt, value))
+}
@@ -0,0 +1,136 @@
+/* writing the framebuffer out as a PNG, without pulling in an image crate.
+ * pixels are the bitplane values OutputState::vram holds, 0-3. PBMs come
+ * from chip8_core::pbm */
+
+
+/* grey levels for off, plane 1, plane 2 and both planes */
+const GREYS: [u8; 4] = [0x00, 0xff, 0x80, 0xc0];
+
+/* 8-bit greyscale PNG. the image data is zlib with uncompressed deflate
+ * blocks, which every decoder has to accept */
+pub fn png(pixels: &[u8], width: usize, height: usize) -> Vec<u8> {
+    /* each row starts with filter type 0, none */
+    let mut raw = Vec::with_capacity((width + 1) * height);
+    for row in pixels.chunks_exact(width).take(height) {
+        raw.push(0);
+        raw.extend(row.iter().map(|&p| GREYS[(p & 0x03) as usize]));
+    }
+
+    let mut ihdr = Vec::new();
+    ihdr.extend_from_slice(&(width as u32).to_be_bytes());
+    ihdr.extend_from_slice(&(height as u32).to_be_bytes());
+    /* bit depth 8, greyscale, deflate, no filtering, not interlaced */
+    ihdr.extend_from_slice(&[8, 0, 0, 0, 0]);
+
+    let mut out = b"\x89PNG\r\n\x1a\n".to_vec();
+    chunk(&mut out, b"IHDR", &ihdr);
+    chunk(&mut out, b"IDAT", &zlib_stored(&raw));
+    chunk(&mut out, b"IEND", &[]);
+    out
+}
+
+fn chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = out.len();
+    out.extend_from_slice(kind);
+    out.extend_from_slice(data);
+    let crc = crc32(&out[start..]);
+    out.extend_from_slice(&crc.to_be_bytes());
+}
+
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    /* deflate, 32K window, no dictionary, fastest */
+    let mut out = vec![0x78, 0x01];
+    let blocks: Vec<&[u8]> = data.chunks(0xffff).collect();
+
+    if blocks.is_empty() {
+        out.extend_from_slice(&[0x01, 0x00, 0x00, 0xff, 0xff]);
+    }
+
+    for (index, block) in blocks.iter().enumerate() {
+        let last = index + 1 == blocks.len();
+        let len = block.len() as u16;
+        out.push(last as u8);
+        out.extend_from_slice(&len.to_le_bytes());
+        out.extend_from_slice(&(!len).to_le_bytes());
+        out.extend_from_slice(block);
+    }
+
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xffffffffu32;
+
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xedb88320 } else { crc >> 1 };
+        }
+    }
+
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+
+    for &byte in data {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+
+    b << 16 | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checksums() {
+        assert_eq!(crc32(b"123456789"), 0xcbf43926);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e60398);
+        assert_eq!(adler32(&[]), 1);
+    }
+
+    #[test]
+    fn png_matches_a_known_encoding() {
+        /* a 2x2 image with a pixel of each plane combination, checked
+         * against Python's zlib.compress(level=0) and zlib.crc32 */
+        let expected = [
+            0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a,
+            0x00, 0x00, 0x00, 0x0d, 0x49, 0x48, 0x44, 0x52,
+            0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x02, 0x08, 0x00, 0x00, 0x00, 0x00,
+            0x57, 0xdd, 0x52, 0xf8,
+            0x00, 0x00, 0x00, 0x11, 0x49, 0x44, 0x41, 0x54,
+            0x78, 0x01, 0x01, 0x06, 0x00, 0xf9, 0xff,
+            0x00, 0x00, 0xff, 0x00, 0x80, 0xc0,
+            0x05, 0xc2, 0x02, 0x40,
+            0xef, 0xaf, 0x72, 0x35,
+            0x00, 0x00, 0x00, 0x00, 0x49, 0x45, 0x4e, 0x44,
+            0xae, 0x42, 0x60, 0x82,
+        ];
+
+        assert_eq!(png(&[0, 1, 2, 3], 2, 2), expected);
+    }
+
+    #[test]
+    fn zlib_stored_of_nothing_is_one_empty_block() {
+        assert_eq!(zlib_stored(&[]), [0x78, 0x01, 0x01, 0x00, 0x00, 0xff, 0xff, 0x00, 0x00, 0x00, 0x01]);
+    }
+
+    #[test]
+    fn zlib_stored_splits_blocks_at_64k() {
+        let data = vec![0xaa; 0x10000];
+        let out = zlib_stored(&data);
+
+        /* a full block that isn't the last, then a final one of a byte */
+        assert_eq!(&out[2..7], &[0x00, 0xff, 0xff, 0x00, 0x00]);
+        let second = 7 + 0xffff;
+        assert_eq!(&out[second..second + 5], &[0x01, 0x01, 0x00, 0xfe, 0xff]);
+        assert_eq!(out.len(), second + 5 + 1 + 4);
+        assert_eq!(out[out.len() - 4..], adler32(&data).to_be_bytes());
+    }
+}
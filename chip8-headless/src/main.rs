@@ -0,0 +1,225 @@
+use std::fs;
+use std::io;
+use std::io::Write;
+use std::process;
+
+use chip8_core::Cpu;
+use chip8_core::CpuError;
+use chip8_core::Instruction;
+use chip8_core::Movie;
+use chip8_core::pbm;
+use chip8_core::RandomAlgorithm;
+use chip8_core::Rng;
+
+mod args;
+mod image;
+mod script;
+
+use args::Args;
+use script::Script;
+
+
+/* why the run ended */
+enum Stop {
+    Frames,
+    Pc,
+    SelfJump,
+    Exit,
+    Error(CpuError),
+}
+
+impl Stop {
+    fn name(&self) -> &'static str {
+        match self {
+            Stop::Frames => "frames",
+            Stop::Pc => "pc",
+            Stop::SelfJump => "self-jump",
+            Stop::Exit => "exit",
+            Stop::Error(_) => "error",
+        }
+    }
+}
+
+/* where the keys come from each frame */
+enum Input {
+    None,
+    Script(Script),
+    Movie(Movie),
+}
+
+fn main() {
+    let args = Args::parse().unwrap_or_else(|err| {
+        eprintln!("{}\n\n{}", err, args::USAGE);
+        process::exit(2);
+    });
+
+    let rom = fs::read(&args.rom).unwrap_or_else(|err| fail(&args.rom, err));
+
+    let mut cpu = Cpu::new(args.platform);
+    if let Some(quirks) = args.quirks {
+        cpu.set_quirks(quirks);
+    }
+    if let Some(ipf) = args.instructions_per_frame {
+        cpu.set_instructions_per_frame(ipf);
+    }
+    cpu.set_rng(Rng::new(RandomAlgorithm::default(), args.seed));
+    cpu.load_rom_bytes(&rom);
+
+    let input = if let Some(path) = &args.input {
+        let text = fs::read_to_string(path).unwrap_or_else(|err| fail(path, err));
+        Input::Script(Script::parse(&text).unwrap_or_else(|err| fail(path, err)))
+    } else if let Some(path) = &args.movie {
+        let data = fs::read(path).unwrap_or_else(|err| fail(path, err));
+        let movie = Movie::from_bytes(&data).unwrap_or_else(|err| fail(path, err));
+        movie.start(&mut cpu).unwrap_or_else(|err| fail(path, err));
+        Input::Movie(movie)
+    } else {
+        Input::None
+    };
+
+    let frames = match (&input, args.frames) {
+        (_, Some(frames)) => frames,
+        (Input::Movie(movie), None) => movie.frames.len() as u64,
+        _ => 600,
+    };
+
+    let (stop, frame) = run(&mut cpu, &input, frames, args.until_pc);
+
+    match &stop {
+        Stop::Error(err) => eprintln!("frame {}: {}", frame, err),
+        _ => eprintln!("stopped after {} frames: {}", frame, stop.name()),
+    }
+
+    if let Some(path) = &args.screenshot {
+        let output = cpu.output();
+        let data = if path.ends_with(".png") {
+            image::png(output.vram, output.width, output.height)
+        } else if path.ends_with(".pbm") {
+            pbm(output.vram, output.width, output.height).into_bytes()
+        } else {
+            fail(path, "screenshots have to be .png or .pbm")
+        };
+        fs::write(path, data).unwrap_or_else(|err| fail(path, err));
+    }
+
+    if let Some(path) = &args.dump {
+        let json = dump(&cpu, &stop, frame);
+        let written = if path == "-" {
+            io::stdout().write_all(json.as_bytes())
+        } else {
+            fs::write(path, json)
+        };
+        written.unwrap_or_else(|err| fail(path, err));
+    }
+
+    if let Stop::Error(_) = stop {
+        process::exit(1);
+    }
+}
+
+/* run frames like Cpu::run_frame() does, but an instruction at a time so
+ * pc can be checked before each one. returns why it stopped and the number
+ * of frames that ran in full */
+fn run(cpu: &mut Cpu, input: &Input, frames: u64, until_pc: Option<usize>) -> (Stop, u64) {
+    for frame in 0..frames {
+        match input {
+            Input::None => {},
+            Input::Script(script) => cpu.keypad.set_bits(script.keys(frame)),
+            Input::Movie(movie) => {
+                movie.play_frame(cpu, frame as usize);
+            },
+        }
+
+        for _ in 0..cpu.instructions_per_frame() {
+            if Some(cpu.pc()) == until_pc {
+                return (Stop::Pc, frame);
+            }
+            if self_jump(cpu) {
+                return (Stop::SelfJump, frame);
+            }
+
+            match cpu.emulate_cycle() {
+                Ok(output) if output.halted => return (Stop::Exit, frame),
+                Ok(_) => {},
+                Err(err) => return (Stop::Error(err), frame),
+            }
+        }
+
+        cpu.vblank();
+    }
+
+    (Stop::Frames, frames)
+}
+
+/* a 1NNN jumping to itself never gets anywhere */
+fn self_jump(cpu: &Cpu) -> bool {
+    let pc = cpu.pc();
+
+    match cpu.memory().get(pc..pc + 2) {
+        Some(bytes) => {
+            let opcode = (bytes[0] as u16) << 8 | bytes[1] as u16;
+            Instruction::decode(opcode) == Some(Instruction::Jmp { nnn: pc as u16 })
+        },
+        None => false,
+    }
+}
+
+/* the registers and why the run stopped, as JSON */
+fn dump(cpu: &Cpu, stop: &Stop, frame: u64) -> String {
+    let error = match stop {
+        Stop::Error(err) => format!("\"{}\"", escape(&err.to_string())),
+        _ => "null".to_string(),
+    };
+    let list = |values: Vec<String>| values.join(", ");
+    let output = cpu.output();
+
+    format!("{{
+  \"rom_hash\": \"{:016x}\",
+  \"platform\": \"{}\",
+  \"frames\": {},
+  \"stop\": \"{}\",
+  \"error\": {},
+  \"pc\": {},
+  \"i\": {},
+  \"v\": [{}],
+  \"stack\": [{}],
+  \"delay_timer\": {},
+  \"sound_timer\": {},
+  \"width\": {},
+  \"height\": {}
+}}
+",
+            cpu.rom_hash(),
+            cpu.platform().name,
+            frame,
+            stop.name(),
+            error,
+            cpu.pc(),
+            cpu.i(),
+            list(cpu.registers().iter().map(|v| v.to_string()).collect()),
+            list(cpu.stack().iter().map(|addr| addr.to_string()).collect()),
+            cpu.delay_timer(),
+            cpu.sound_timer(),
+            output.width,
+            output.height)
+}
+
+fn escape(s: &str) -> String {
+    let mut out = String::new();
+
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+
+    out
+}
+
+fn fail(path: &str, err: impl std::fmt::Display) -> ! {
+    eprintln!("{}: {}", path, err);
+    process::exit(2);
+}
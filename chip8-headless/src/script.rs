@@ -0,0 +1,98 @@
+/* scripted keypad input. each line is a frame number followed by the hex
+ * keys held from that frame on, until the next line. # starts a comment:
+ *
+ *   # wait for the title screen, then hold 5 for a second
+ *   0
+ *   120 5
+ *   180
+ *   200 4 6
+ */
+pub struct Script {
+    /* (frame, Keypad::bits()), in frame order */
+    changes: Vec<(u64, u16)>,
+}
+
+impl Script {
+    pub fn parse(text: &str) -> Result<Script, String> {
+        let mut changes: Vec<(u64, u16)> = Vec::new();
+
+        for (index, line) in text.lines().enumerate() {
+            let line_number = index + 1;
+            let line = line.split('#').next().unwrap_or_default();
+            let mut words = line.split_whitespace();
+
+            let Some(frame) = words.next() else {
+                continue;
+            };
+            let frame: u64 = frame.parse()
+                                .map_err(|_| format!("line {}: '{}' is not a frame number", line_number, frame))?;
+
+            if changes.last().is_some_and(|&(last, _)| frame <= last) {
+                return Err(format!("line {}: frame {} is out of order", line_number, frame));
+            }
+
+            let mut bits = 0;
+            for key in words {
+                let index = u16::from_str_radix(key, 16)
+                                .ok()
+                                .filter(|&index| key.len() == 1 && index < 16)
+                                .ok_or_else(|| format!("line {}: '{}' is not a key 0-F", line_number, key))?;
+                bits |= 1 << index;
+            }
+
+            changes.push((frame, bits));
+        }
+
+        Ok(Script { changes })
+    }
+
+    /* the keys held during a frame, as Keypad::bits() */
+    pub fn keys(&self, frame: u64) -> u16 {
+        match self.changes.partition_point(|&(start, _)| start <= frame) {
+            0 => 0,
+            index => self.changes[index - 1].1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Script;
+
+    #[test]
+    fn keys_are_held_until_the_next_line() {
+        let script = Script::parse("# a comment\n\n10 5\n20 4 6  # two keys\n30\n").unwrap();
+
+        assert_eq!(script.keys(0), 0);
+        assert_eq!(script.keys(9), 0);
+        assert_eq!(script.keys(10), 1 << 5);
+        assert_eq!(script.keys(19), 1 << 5);
+        assert_eq!(script.keys(20), 1 << 4 | 1 << 6);
+        assert_eq!(script.keys(30), 0);
+        assert_eq!(script.keys(1000), 0);
+    }
+
+    #[test]
+    fn keys_are_hex() {
+        let script = Script::parse("0 a F 0").unwrap();
+        assert_eq!(script.keys(0), 1 << 0xa | 1 << 0xf | 1);
+    }
+
+    #[test]
+    fn an_empty_script_holds_nothing() {
+        assert_eq!(Script::parse("").unwrap().keys(0), 0);
+    }
+
+    #[test]
+    fn errors_give_the_line() {
+        let error = |text| Script::parse(text).err().unwrap();
+
+        assert_eq!(error("0\nsoon 5"), "line 2: 'soon' is not a frame number");
+        assert_eq!(error("0\n\n-1"), "line 3: '-1' is not a frame number");
+        assert_eq!(error("10\n# rewind\n10 1"), "line 3: frame 10 is out of order");
+        assert_eq!(error("10\n5 1"), "line 2: frame 5 is out of order");
+        assert_eq!(error("0 10"), "line 1: '10' is not a key 0-F");
+        assert_eq!(error("0 g"), "line 1: 'g' is not a key 0-F");
+        assert_eq!(error("0 +1"), "line 1: '+1' is not a key 0-F");
+    }
+}
@@ -0,0 +1,108 @@
+use chip8_core::Instruction;
+use chip8_core::Platform;
+
+/* the mnemonic for a decoded instruction, see instruction-set.txt. operand is
+ * whatever follows the opcode for instructions longer than 2 bytes */
+fn mnemonic(instr: &Instruction, operand: &[u8]) -> String {
+    match *instr {
+        Instruction::Nop => "NOP".to_string(),
+        Instruction::Cls => "CLS".to_string(),
+        Instruction::Rts => "RTS".to_string(),
+        Instruction::ScrollDown { n } => format!("SCROLL.D #${:X}", n),
+        Instruction::ScrollUp { n } => format!("SCROLL.U #${:X}", n),
+        Instruction::ScrollRight => "SCROLL.R".to_string(),
+        Instruction::ScrollLeft => "SCROLL.L".to_string(),
+        Instruction::Exit => "EXIT".to_string(),
+        Instruction::Lores => "LORES".to_string(),
+        Instruction::Hires => "HIRES".to_string(),
+        Instruction::Jmp { nnn } => format!("JMP ${:03X}", nnn),
+        Instruction::Call { nnn } => format!("CALL ${:03X}", nnn),
+        Instruction::SkipEqImm { x, nn } => format!("SKIP.EQ V{:X},#${:02X}", x, nn),
+        Instruction::SkipNeImm { x, nn } => format!("SKIP.NE V{:X},#${:02X}", x, nn),
+        Instruction::SkipEq { x, y } => format!("SKIP.EQ V{:X},V{:X}", x, y),
+        Instruction::StoreRange { x, y } => format!("MOVM (I),V{:X}-V{:X}", x, y),
+        Instruction::LoadRange { x, y } => format!("MOVM V{:X}-V{:X},(I)", x, y),
+        Instruction::Mvi { x, nn } => format!("MVI V{:X},#${:02X}", x, nn),
+        Instruction::Adi { x, nn } => format!("ADI V{:X},#${:02X}", x, nn),
+        Instruction::Mov { x, y } => format!("MOV. V{:X},V{:X}", x, y),
+        Instruction::Or { x, y } => format!("OR. V{:X},V{:X}", x, y),
+        Instruction::And { x, y } => format!("AND. V{:X},V{:X}", x, y),
+        Instruction::Xor { x, y } => format!("XOR. V{:X},V{:X}", x, y),
+        Instruction::Add { x, y } => format!("ADD. V{:X},V{:X}", x, y),
+        Instruction::Sub { x, y } => format!("SUB. V{:X},V{:X}", x, y),
+        Instruction::Shr { x, y } => format!("SHR. V{:X},V{:X}", x, y),
+        Instruction::Subb { x, y } => format!("SUBB. V{:X},V{:X}", x, y),
+        Instruction::Shl { x, y } => format!("SHL. V{:X},V{:X}", x, y),
+        Instruction::SkipNe { x, y } => format!("SKIP.NE V{:X},V{:X}", x, y),
+        Instruction::MviI { nnn } => format!("MVI I,#${:03X}", nnn),
+        Instruction::JmpV0 { nnn } => format!("JMP ${:03X}(V0)", nnn),
+        Instruction::Rndmsk { x, nn } => format!("RNDMSK V{:X},$%{:02X}", x, nn),
+        Instruction::Sprite { x, y, n } => {
+            format!("SPRITE V{:X},V{:X},#${:X}", x, y, n)
+        },
+        Instruction::SkipKeyY { x } => format!("SKIPKEY.Y V{:X}", x),
+        Instruction::SkipKeyN { x } => format!("SKIPKEY.N V{:X}", x),
+        Instruction::LongI => format!("MVI.L I,#${:02X}{:02X}", operand[0], operand[1]),
+        Instruction::Plane { x } => format!("PLANE #${:X}", x),
+        Instruction::Audio => "AUDIO (I)".to_string(),
+        Instruction::GetDelay { x } => format!("MOV V{:X},DELAY", x),
+        Instruction::Key { x } => format!("KEY V{:X}", x),
+        Instruction::SetDelay { x } => format!("MOV DELAY,V{:X}", x),
+        Instruction::SetSound { x } => format!("MOV SOUND,V{:X}", x),
+        Instruction::AdiI { x } => format!("ADI I,V{:X}", x),
+        Instruction::SpriteChar { x } => format!("SPRITECHAR I,V{:X}", x),
+        Instruction::BigSpriteChar { x } => format!("BIGSPRITECHAR I,V{:X}", x),
+        Instruction::MovBcd { x } => format!("MOVBCD (I),V{:X}", x),
+        Instruction::Pitch { x } => format!("MOV PITCH,V{:X}", x),
+        Instruction::Store { x } => format!("MOVM (I),V0-V{:X}", x),
+        Instruction::Load { x } => format!("MOVM V0-V{:X},(I)", x),
+        Instruction::StoreFlags { x } => format!("MOVM (R),V0-V{:X}", x),
+        Instruction::LoadFlags { x } => format!("MOVM V0-V{:X},(R)", x),
+    }
+}
+
+/* the listing line for the instruction at the start of bytes, which has to
+ * hold at least 2, and its size */
+fn line(bytes: &[u8], pc: usize, platform: &Platform) -> (String, usize)
+{
+    /* pc, instruction */
+    let mut line = format!("{:04X?} {:02X?} {:02X?} ", pc, bytes[0], bytes[1]);
+
+    /* the unknowns may just be data which is fine bc the instruction pointer
+     * should never point there
+     * https://stackoverflow.com/questions/37368412/how-do-deal-with-impossible-chip-8-instructions
+     */
+    let opcode = (bytes[0] as u16) << 8 | bytes[1] as u16;
+
+    match Instruction::decode(opcode) {
+        Some(instr) if platform.supports(&instr) && instr.size() <= bytes.len() => {
+            let operand = &bytes[2..instr.size()];
+            for byte in operand {
+                line.push_str(&format!("{:02X?} ", byte));
+            }
+            line.push_str(&mnemonic(&instr, operand));
+            (line, instr.size())
+        },
+        _ => (line, 2),
+    }
+}
+
+/* the listing for a whole ROM, one line per instruction, addressed from
+ * 0x200 where it gets loaded */
+pub fn disassemble(rom: &[u8], platform: &Platform) -> String {
+    let mut listing = String::new();
+
+    /* the instructions are at least 2 bytes. a trailing odd byte can't be
+     * an instruction so it's dropped */
+    let mut pc: usize = 0;
+
+    while pc + 1 < rom.len() {
+        /* add 0x200 here for the offset */
+        let (line, size) = line(&rom[pc..], pc + 0x200, platform);
+        listing.push_str(&line);
+        listing.push('\n');
+        pc += size;
+    }
+
+    listing
+}
@@ -0,0 +1,89 @@
+/* frame time of drawing the screen the old way, a fill_rect per pixel, and
+ * with render::Screen's streaming texture. both draw to an offscreen
+ * software canvas the size of the default window, so no display is needed:
+ *
+ *   cargo bench -p emulator
+ */
+
+use std::hint::black_box;
+use std::time::Duration;
+use std::time::Instant;
+
+use sdl2::pixels::Color;
+use sdl2::pixels::PixelFormatEnum;
+use sdl2::rect::Rect;
+use sdl2::render::Canvas;
+use sdl2::surface::Surface;
+
+#[path = "../src/render.rs"]
+mod render;
+
+use render::Screen;
+
+
+const FRAMES: u32 = 200;
+
+const PALETTE: [Color; 4] = [Color::RGB(0, 0, 0),
+                             Color::RGB(0, 255, 0),
+                             Color::RGB(0, 128, 255),
+                             Color::RGB(255, 255, 255)];
+
+/* a checkerboard that shifts every frame, so every pixel changes */
+fn frame(width: usize, height: usize, n: usize) -> Vec<u8> {
+    (0..width * height).map(|i| ((i % width + i / width + n) % 2) as u8).collect()
+}
+
+/* what Display::draw_screen used to do */
+fn fill_rects(canvas: &mut Canvas<Surface>, pixels: &[u8], width: usize) {
+    let (canvas_width, _) = canvas.output_size().unwrap();
+    let scale = canvas_width / width as u32;
+
+    for (y, row) in pixels.chunks_exact(width).enumerate() {
+        for (x, &col) in row.iter().enumerate() {
+            canvas.set_draw_color(PALETTE[(col & 0x03) as usize]);
+            let _ = canvas.fill_rect(Rect::new((x as u32 * scale) as i32,
+                                               (y as u32 * scale) as i32,
+                                               scale,
+                                               scale));
+        }
+    }
+}
+
+fn time(mut draw: impl FnMut(usize)) -> Duration {
+    /* warm up */
+    draw(0);
+
+    let start = Instant::now();
+    for n in 0..FRAMES as usize {
+        draw(n);
+    }
+    start.elapsed() / FRAMES
+}
+
+fn main() {
+    let surface = Surface::new(1280, 640, PixelFormatEnum::RGB888).unwrap();
+    let mut canvas = surface.into_canvas().unwrap();
+    let creator = canvas.texture_creator();
+
+    for (width, height) in [(64, 32), (128, 64)] {
+        let frames: Vec<Vec<u8>> = (0..2).map(|n| frame(width, height, n)).collect();
+
+        let old = time(|n| {
+            fill_rects(&mut canvas, &frames[n % 2], width);
+            black_box(&canvas);
+        });
+
+        let mut screen = Screen::new();
+        let new = time(|n| {
+            screen.upload(&creator, &frames[n % 2], width, height, &PALETTE).unwrap();
+            screen.draw(&mut canvas).unwrap();
+            black_box(&canvas);
+        });
+
+        println!("{}x{}: fill_rect {:>8.3} ms/frame, texture {:>8.3} ms/frame, {:.1}x faster",
+                 width, height,
+                 old.as_secs_f64() * 1000.0,
+                 new.as_secs_f64() * 1000.0,
+                 old.as_secs_f64() / new.as_secs_f64());
+    }
+}
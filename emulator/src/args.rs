@@ -0,0 +1,276 @@
+use std::env;
+use std::str::FromStr;
+
+use chip8_core::Platform;
+use chip8_core::Quirks;
+
+use crate::audio::Tone;
+use crate::audio::Waveform;
+use sdl2::pixels::Color;
+
+use crate::display;
+use crate::display::Options;
+use crate::display::Palette;
+use crate::display::Persistence;
+
+
+pub const USAGE: &str = "\
+usage: emulator [options] <rom>
+
+options:
+    --platform <name>   machine to emulate: vip (default), chip48, schip,
+                        xochip
+    --quirks <list>     comma separated quirks to enable, or \"none\",
+                        replacing the platform's: shift, memory, jump,
+                        vfreset, clip, vblank, overflow. vip turns on
+                        all but jump and overflow
+    --ipf <n>           instructions per 60Hz frame, replacing the
+                        platform's clock speed
+    --seed <n>          seed for the random numbers, so a run can be
+                        repeated. picked from the clock by default
+    --palette <colours> green (default), amber, lcd, vip-white, or up to
+                        four comma separated RRGGBB colours for background,
+                        plane 1, plane 2 and both planes
+    --fg <colour>       RRGGBB colour for lit pixels, over the palette's
+    --bg <colour>       RRGGBB colour for the background
+    --scale <n>         window pixels per CHIP-8 pixel, default 20
+    --fullscreen        start fullscreen
+    --persistence <mode>
+                        keep pixels on screen after they go out, against
+                        flicker: off (default), phosphor[:frames] to fade
+                        them out, 8 frames by default, or deflicker to show
+                        the last two frames together
+    --tone <hz>         frequency of the beep, default 440
+    --volume <percent>  sound volume 0-100, default 25
+    --waveform <wave>   shape of the beep: square (default), sine, triangle
+    --config <file>     config file, default
+                        $XDG_CONFIG_HOME/chip8-emu/config.ini
+    --rewind-seconds <n>
+                        how far back backspace can rewind, default 10.
+                        0 turns rewinding off
+    --record <file>     record the keys pressed each frame to a movie
+    --play <file>       play back a movie recorded from the same ROM
+    --on-error <action> when the program crashes: halt (default) stops it,
+                        ignore skips the bad instruction, debug opens a
+                        debugger on stdin
+
+keys:
+    M                   mute/unmute sound
+    F1-F10              load save state 1-10
+    shift+F1-F10        save state 1-10
+    backspace           hold to rewind
+    F11                 fullscreen on/off
+    - and =             make the window smaller/bigger
+    P                   next palette
+    O                   next persistence mode";
+
+/* command line options */
+pub struct Args {
+    pub rom: String,
+    pub platform: Platform,
+    /* overrides the platform's quirks */
+    pub quirks: Option<Quirks>,
+    /* overrides the platform's clock speed */
+    pub instructions_per_frame: Option<u32>,
+    pub seed: Option<u64>,
+    /* override the [display] config sections */
+    pub palette: Option<Palette>,
+    pub foreground: Option<Color>,
+    pub background: Option<Color>,
+    pub scale: Option<u32>,
+    pub fullscreen: bool,
+    pub persistence: Option<Persistence>,
+    pub tone: Tone,
+    pub on_error: OnError,
+    pub rewind_seconds: u32,
+    /* movie files to record to and play back */
+    pub record: Option<String>,
+    pub play: Option<String>,
+    /* the config file, if not the default one */
+    pub config: Option<String>,
+}
+
+/* what to do when the program hits a CpuError */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum OnError {
+    Halt,
+    Ignore,
+    Debug,
+}
+
+impl FromStr for OnError {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "halt" => Ok(OnError::Halt),
+            "ignore" => Ok(OnError::Ignore),
+            "debug" => Ok(OnError::Debug),
+            _ => Err(format!("unknown error action '{}', expected halt, ignore or debug", s)),
+        }
+    }
+}
+
+impl Args {
+    pub fn parse() -> Result<Args, String> {
+        let mut rom = None;
+        let mut platform = Platform::default();
+        let mut quirks = None;
+        let mut instructions_per_frame = None;
+        let mut seed = None;
+        let mut palette = None;
+        let mut foreground = None;
+        let mut background = None;
+        let mut scale = None;
+        let mut fullscreen = false;
+        let mut persistence = None;
+        let mut tone = Tone::default();
+        let mut on_error = OnError::Halt;
+        let mut rewind_seconds = 10;
+        let mut record = None;
+        let mut play = None;
+        let mut config = None;
+
+        let mut args = env::args().skip(1);
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--platform" => {
+                    platform = value(&mut args, &arg)?.parse()?;
+                },
+                "--quirks" => {
+                    quirks = Some(value(&mut args, &arg)?.parse()?);
+                },
+                "--ipf" => {
+                    instructions_per_frame = Some(number(&value(&mut args, &arg)?, &arg)
+                                                    .ok()
+                                                    .filter(|&ipf: &u32| ipf > 0)
+                                                    .ok_or("--ipf expects a number above 0")?);
+                },
+                "--seed" => {
+                    seed = Some(number(&value(&mut args, &arg)?, &arg)?);
+                },
+                "--palette" => {
+                    palette = Some(display::parse_palette(&value(&mut args, &arg)?)?);
+                },
+                "--fg" => {
+                    foreground = Some(display::parse_color(&value(&mut args, &arg)?)?);
+                },
+                "--bg" => {
+                    background = Some(display::parse_color(&value(&mut args, &arg)?)?);
+                },
+                "--scale" => {
+                    scale = Some(number(&value(&mut args, &arg)?, &arg)
+                                    .ok()
+                                    .filter(|&scale: &u32| scale > 0)
+                                    .ok_or("--scale expects a number above 0")?);
+                },
+                "--fullscreen" => {
+                    fullscreen = true;
+                },
+                "--persistence" => {
+                    persistence = Some(value(&mut args, &arg)?.parse()?);
+                },
+                "--tone" => {
+                    tone.frequency = number(&value(&mut args, &arg)?, &arg)
+                                        .ok()
+                                        .filter(|&hz: &f32| hz.is_finite() && hz > 0.0)
+                                        .ok_or("--tone expects a frequency above 0")?;
+                },
+                "--volume" => {
+                    let percent: f32 = number(&value(&mut args, &arg)?, &arg)
+                                        .ok()
+                                        .filter(|percent: &f32| (0.0..=100.0).contains(percent))
+                                        .ok_or("--volume expects a percentage from 0 to 100")?;
+                    tone.volume = percent / 100.0;
+                },
+                "--waveform" => {
+                    tone.waveform = value(&mut args, &arg)?.parse::<Waveform>()?;
+                },
+                "--config" => {
+                    config = Some(value(&mut args, &arg)?);
+                },
+                "--rewind-seconds" => {
+                    rewind_seconds = number(&value(&mut args, &arg)?, &arg)?;
+                },
+                "--record" => {
+                    record = Some(value(&mut args, &arg)?);
+                },
+                "--play" => {
+                    play = Some(value(&mut args, &arg)?);
+                },
+                "--on-error" => {
+                    on_error = value(&mut args, &arg)?.parse()?;
+                },
+                _ if arg.starts_with("--") => {
+                    return Err(format!("unknown option '{}'", arg));
+                },
+                _ => rom = Some(arg),
+            }
+        }
+
+        /* a movie starts from the state the game was in when recording
+         * began, which playing another movie replaces. skipped instructions
+         * and the debugger aren't in the movie either, so it couldn't be
+         * played back the way it ran */
+        if record.is_some() && play.is_some() {
+            return Err("--record and --play can't be used together".to_string());
+        }
+        if record.is_some() && on_error != OnError::Halt {
+            return Err("--record only works with --on-error halt".to_string());
+        }
+
+        Ok(Args {
+            rom: rom.ok_or("no rom given")?,
+            platform,
+            quirks,
+            instructions_per_frame,
+            seed,
+            palette,
+            foreground,
+            background,
+            scale,
+            fullscreen,
+            persistence,
+            tone,
+            on_error,
+            rewind_seconds,
+            record,
+            play,
+            config,
+        })
+    }
+}
+
+impl Args {
+    /* put the display options given on the command line over the ones
+     * from the config file */
+    pub fn display_options(&self, options: &mut Options) {
+        if let Some(palette) = self.palette {
+            options.palette = palette;
+        }
+        if let Some(color) = self.foreground {
+            options.palette[1] = color;
+        }
+        if let Some(color) = self.background {
+            options.palette[0] = color;
+        }
+        if let Some(scale) = self.scale {
+            options.scale = scale;
+        }
+        options.fullscreen |= self.fullscreen;
+        if let Some(persistence) = self.persistence {
+            options.persistence = persistence;
+        }
+    }
+}
+
+/* the value following an option */
+fn value(args: &mut impl Iterator<Item = String>, opt: &str) -> Result<String, String> {
+    args.next().ok_or_else(|| format!("{} needs a value", opt))
+}
+
+/* a numeric option value */
+fn number<T: std::str::FromStr>(value: &str, opt: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("{} expects a number, got '{}'", opt, value))
+}
@@ -0,0 +1,188 @@
+use std::f32::consts::PI;
+use std::str::FromStr;
+
+use sdl2::audio::{AudioCallback, AudioDevice, AudioSpecDesired};
+
+
+const SAMPLE_RATE: i32 = 44100;
+
+/* how long the output takes to fade in or out when the sound starts or stops.
+ * cutting a wave off mid cycle is what makes the speaker click */
+const FADE_SECONDS: f32 = 0.005;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Waveform {
+    Square,
+    Sine,
+    Triangle,
+}
+
+impl Waveform {
+    /* the wave's value at phase 0.0-1.0, in -1.0..1.0 */
+    fn sample(&self, phase: f32) -> f32 {
+        match self {
+            Waveform::Square => if phase < 0.5 { 1.0 } else { -1.0 },
+            Waveform::Sine => (phase * 2.0 * PI).sin(),
+            Waveform::Triangle => 1.0 - 4.0 * (phase - 0.5).abs(),
+        }
+    }
+}
+
+impl FromStr for Waveform {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "square" => Ok(Waveform::Square),
+            "sine" => Ok(Waveform::Sine),
+            "triangle" => Ok(Waveform::Triangle),
+            _ => Err(format!("unknown waveform '{}', expected square, sine or triangle", s)),
+        }
+    }
+}
+
+/* the beep played for programs without an XO-CHIP audio pattern */
+#[derive(Clone, Copy, Debug)]
+pub struct Tone {
+    pub frequency: f32,
+    /* 0.0-1.0 */
+    pub volume: f32,
+    pub waveform: Waveform,
+}
+
+impl Default for Tone {
+    fn default() -> Self {
+        Tone { frequency: 440.0, volume: 0.25, waveform: Waveform::Square }
+    }
+}
+
+/* the state the emulation loop hands the audio thread */
+#[derive(Clone, Copy, PartialEq)]
+struct Sound {
+    pattern: Option<[u8; 16]>,
+    pitch: u8,
+    playing: bool,
+    muted: bool,
+}
+
+/* SDL calls this from its audio thread to fill the output buffer */
+struct Speaker {
+    sound: Sound,
+    tone: Tone,
+    sample_rate: f32,
+    /* position in the current cycle of the tone, 0.0-1.0, or the bit of
+     * the 128 bit pattern being played with its fractional part */
+    phase: f32,
+    /* fades between 0.0 and 1.0 as the sound starts and stops */
+    gain: f32,
+}
+
+impl Speaker {
+    /* how far phase moves per output sample */
+    fn step(&self) -> f32 {
+        match self.sound.pattern {
+            Some(_) => {
+                let rate = 4000.0 * 2f32.powf((self.sound.pitch as f32 - 64.0) / 48.0);
+                rate / self.sample_rate
+            },
+            None => self.tone.frequency / self.sample_rate,
+        }
+    }
+
+    /* the current sample at full gain */
+    fn wave(&self) -> f32 {
+        match self.sound.pattern {
+            Some(pattern) => {
+                let bit = self.phase as usize;
+                let on = (pattern[bit / 8] >> (7 - bit % 8)) & 0x01;
+                if on == 1 { 1.0 } else { -1.0 }
+            },
+            None => self.tone.waveform.sample(self.phase),
+        }
+    }
+}
+
+impl AudioCallback for Speaker {
+    type Channel = f32;
+
+    fn callback(&mut self, out: &mut [f32]) {
+        let step = self.step();
+        let period = if self.sound.pattern.is_some() { 128.0 } else { 1.0 };
+        let fade = 1.0 / (FADE_SECONDS * self.sample_rate);
+        let target = if self.sound.playing && !self.sound.muted { 1.0 } else { 0.0 };
+
+        /* the program may have switched between tone and pattern */
+        self.phase %= period;
+
+        for sample in out.iter_mut() {
+            if self.gain < target {
+                self.gain = (self.gain + fade).min(target);
+            } else if self.gain > target {
+                self.gain = (self.gain - fade).max(target);
+            }
+
+            if self.gain == 0.0 {
+                /* start the next sound at the top of its wave */
+                self.phase = 0.0;
+                *sample = 0.0;
+                continue;
+            }
+
+            *sample = self.wave() * self.gain * self.tone.volume;
+            self.phase = (self.phase + step) % period;
+        }
+    }
+}
+
+
+/* plays the sound timer through SDL: the XO-CHIP audio pattern if the program
+ * loaded one, otherwise a plain tone */
+pub struct Audio {
+    device: AudioDevice<Speaker>,
+    /* what the audio thread was last given, so it's only locked on change */
+    sound: Sound,
+}
+
+impl Audio {
+    pub fn new(sdl_ctx: &sdl2::Sdl, tone: Tone) -> Result<Self, String> {
+        let audio = sdl_ctx.audio()?;
+        let spec = AudioSpecDesired {
+            freq: Some(SAMPLE_RATE),
+            channels: Some(1),
+            samples: None,
+        };
+
+        let sound = Sound { pattern: None, pitch: 64, playing: false, muted: false };
+        let device = audio.open_playback(None, &spec, |spec| Speaker {
+            sound,
+            tone,
+            sample_rate: spec.freq as f32,
+            phase: 0.0,
+            gain: 0.0,
+        })?;
+        device.resume();
+
+        Ok(Audio { device, sound })
+    }
+
+    /* play while playing is set, the pattern at the given pitch if there
+     * is one */
+    pub fn update(&mut self, pattern: Option<&[u8; 16]>, pitch: u8, playing: bool) {
+        let sound = Sound { pattern: pattern.copied(), pitch, playing, ..self.sound };
+        self.send(sound);
+    }
+
+    /* returns whether audio is now muted */
+    pub fn toggle_mute(&mut self) -> bool {
+        let sound = Sound { muted: !self.sound.muted, ..self.sound };
+        self.send(sound);
+        sound.muted
+    }
+
+    fn send(&mut self, sound: Sound) {
+        if sound != self.sound {
+            self.device.lock().sound = sound;
+            self.sound = sound;
+        }
+    }
+}
@@ -0,0 +1,100 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+
+/* the config file is INI style:
+ *
+ *   # comment
+ *   [keys]
+ *   layout = numpad
+ *   5 = Up, W
+ *
+ *   [keys:pong.ch8]
+ *   1 = Up
+ *
+ * a section name can be followed by a colon and a ROM's file name or
+ * rom_hash() to only apply to that ROM, on top of the plain section */
+#[derive(Default)]
+pub struct Config {
+    sections: Vec<Section>,
+}
+
+pub struct Section {
+    pub name: String,
+    /* key, value and the line they came from, in file order */
+    pub entries: Vec<(String, String, usize)>,
+}
+
+impl Config {
+    /* the file given with --config, or the default one if there is one */
+    pub fn load(path: Option<&str>) -> Result<Config, String> {
+        let (path, required) = match path {
+            Some(path) => (PathBuf::from(path), true),
+            None => match default_path() {
+                Some(path) => (path, false),
+                None => return Ok(Config::default()),
+            },
+        };
+
+        match fs::read_to_string(&path) {
+            Ok(text) => Config::parse(&text).map_err(|err| format!("{}: {}", path.display(), err)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound && !required => Ok(Config::default()),
+            Err(err) => Err(format!("{}: {}", path.display(), err)),
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Config, String> {
+        let mut sections: Vec<Section> = Vec::new();
+
+        for (index, line) in text.lines().enumerate() {
+            let line_number = index + 1;
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+                continue;
+            }
+
+            if let Some(name) = line.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+                sections.push(Section { name: name.trim().to_string(), entries: Vec::new() });
+                continue;
+            }
+
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(format!("line {}: expected key = value", line_number));
+            };
+
+            let Some(section) = sections.last_mut() else {
+                return Err(format!("line {}: '{}' is outside any [section]", line_number, key.trim()));
+            };
+
+            section.entries.push((key.trim().to_string(), value.trim().to_string(), line_number));
+        }
+
+        Ok(Config { sections })
+    }
+
+    /* the sections that apply to a ROM, in the order they should be
+     * applied: the plain section, then the ones for its file name and
+     * its hash */
+    pub fn sections(&self, name: &str, rom_name: &str, rom_hash: u64) -> Vec<&Section> {
+        let names = [name.to_string(),
+                     format!("{}:{}", name, rom_name),
+                     format!("{}:{:016x}", name, rom_hash)];
+
+        names.iter()
+             .flat_map(|name| self.sections.iter().filter(move |section| &section.name == name))
+             .collect()
+    }
+}
+
+/* $XDG_CONFIG_HOME/chip8-emu/config.ini, or ~/.config if that isn't set */
+fn default_path() -> Option<PathBuf> {
+    let dir = match env::var_os("XDG_CONFIG_HOME").filter(|dir| !dir.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(env::var_os("HOME")?).join(".config"),
+    };
+
+    Some(dir.join("chip8-emu").join("config.ini"))
+}
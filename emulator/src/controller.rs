@@ -0,0 +1,227 @@
+use std::collections::HashMap;
+
+use sdl2::controller::{Axis, Button, GameController};
+use sdl2::event::Event;
+use sdl2::GameControllerSubsystem;
+
+use chip8_core::Keypad;
+
+use crate::config::Section;
+use crate::keymap;
+use crate::keymap::HeldKeys;
+
+
+/* how far the left stick has to be pushed to count as the D-pad */
+const STICK_DEADZONE: i16 = 16384;
+
+/* the D-pad steers with 2/4/6/8, what most games use for directions. A is
+ * 5, usually fire, and the other face buttons the keys next to it */
+const DIGITS_2468: [(Button, usize); 8] = [
+    (Button::DPadUp, 0x2), (Button::DPadDown, 0x8),
+    (Button::DPadLeft, 0x4), (Button::DPadRight, 0x6),
+    (Button::A, 0x5), (Button::B, 0x0), (Button::X, 0x7), (Button::Y, 0x9),
+];
+
+/* the D-pad is 5/7/8/9, the WASD keys on the QWERTY keypad layout */
+const DIGITS_5789: [(Button, usize); 8] = [
+    (Button::DPadUp, 0x5), (Button::DPadDown, 0x8),
+    (Button::DPadLeft, 0x7), (Button::DPadRight, 0x9),
+    (Button::A, 0x6), (Button::B, 0x4), (Button::X, 0x1), (Button::Y, 0x2),
+];
+
+/* two player games like pong give each player a column at the edge of the
+ * keypad, 1/4/7/A on the left and C/D/E/F on the right. the D-pad's up and
+ * down press the top two keys of the column, A and B the bottom two */
+const LEFT_COLUMN: [(Button, usize); 4] = [
+    (Button::DPadUp, 0x1), (Button::DPadDown, 0x4), (Button::A, 0x7), (Button::B, 0xa),
+];
+
+const RIGHT_COLUMN: [(Button, usize); 4] = [
+    (Button::DPadUp, 0xc), (Button::DPadDown, 0xd), (Button::A, 0xe), (Button::B, 0xf),
+];
+
+pub const LAYOUTS: [&str; 4] = ["2468", "5789", "left", "right"];
+
+/* what each player's controller starts out as. a second player is only
+ * needed in two player games, and those put them on the right */
+pub const DEFAULT_LAYOUTS: [&str; 2] = ["2468", "right"];
+
+/* which CHIP-8 key each controller button presses */
+pub struct ControllerMap {
+    buttons: HashMap<Button, usize>,
+}
+
+impl ControllerMap {
+    /* one of the built in LAYOUTS */
+    pub fn layout(name: &str) -> Result<ControllerMap, String> {
+        let buttons: &[(Button, usize)] = match name {
+            "2468" => &DIGITS_2468,
+            "5789" => &DIGITS_5789,
+            "left" => &LEFT_COLUMN,
+            "right" => &RIGHT_COLUMN,
+            _ => return Err(format!("unknown controller layout '{}', expected one of: {}",
+                                    name, LAYOUTS.join(", "))),
+        };
+
+        Ok(ControllerMap { buttons: buttons.iter().copied().collect() })
+    }
+
+    /* apply a [controller1] or [controller2] config section, the same way
+     * as [keys] but with SDL's button names: a, b, x, y, back, start,
+     * leftshoulder, rightshoulder, dpup, dpdown, dpleft, dpright... */
+    pub fn apply(&mut self, section: &Section) -> Result<(), String> {
+        for (name, value, line) in &section.entries {
+            let err = |msg: String| format!("line {}: {}", line, msg);
+
+            if name == "layout" {
+                *self = ControllerMap::layout(value).map_err(err)?;
+                continue;
+            }
+
+            let index = keymap::chip8_key(name)
+                            .ok_or_else(|| err(format!("'{}' is not a key 0-F", name)))?;
+
+            self.buttons.retain(|_, &mut mapped| mapped != index);
+
+            for button_name in value.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+                let button = Button::from_string(button_name)
+                                .ok_or_else(|| err(format!("unknown button '{}'", button_name)))?;
+                self.buttons.insert(button, index);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/* a plugged in controller */
+struct Pad {
+    controller: GameController,
+    /* the CHIP-8 keys it's holding down, released if it's unplugged */
+    down: Vec<usize>,
+    /* the D-pad direction the left stick is pushed in, on each axis */
+    stick: [Option<Button>; 2],
+}
+
+/* up to two controllers, for player 1 and 2, which can be plugged in and
+ * out while the emulator is running */
+pub struct Controllers {
+    subsystem: GameControllerSubsystem,
+    pads: [Option<Pad>; 2],
+    maps: [ControllerMap; 2],
+}
+
+impl Controllers {
+    /* SDL reports the controllers already plugged in as being added, so
+     * they are picked up by handle() like any others */
+    pub fn new(sdl_ctx: &sdl2::Sdl, maps: [ControllerMap; 2]) -> Result<Self, String> {
+        let subsystem = sdl_ctx.game_controller()?;
+        Ok(Controllers { subsystem, pads: [None, None], maps })
+    }
+
+    pub fn handle(&mut self, event: &Event, held: &mut HeldKeys, keypad: &mut Keypad) {
+        match *event {
+            Event::ControllerDeviceAdded { which, .. } => self.add(which),
+            Event::ControllerDeviceRemoved { which, .. } => {
+                if let Some(player) = self.player(which) {
+                    if let Some(pad) = self.pads[player].take() {
+                        for key in pad.down {
+                            held.release(keypad, key);
+                        }
+                        println!("controller {} unplugged", player + 1);
+                    }
+                }
+            },
+            Event::ControllerButtonDown { which, button, .. } => {
+                if let Some(player) = self.player(which) {
+                    self.button(player, button, true, held, keypad);
+                }
+            },
+            Event::ControllerButtonUp { which, button, .. } => {
+                if let Some(player) = self.player(which) {
+                    self.button(player, button, false, held, keypad);
+                }
+            },
+            Event::ControllerAxisMotion { which, axis, value, .. } => {
+                if let Some(player) = self.player(which) {
+                    self.stick(player, axis, value, held, keypad);
+                }
+            },
+            _ => {},
+        }
+    }
+
+    /* give a newly plugged in controller to the first player without one */
+    fn add(&mut self, joystick_index: u32) {
+        let Some(player) = self.pads.iter().position(Option::is_none) else {
+            return;
+        };
+
+        match self.subsystem.open(joystick_index) {
+            Ok(controller) => {
+                println!("controller {}: {}", player + 1, controller.name());
+                self.pads[player] = Some(Pad { controller, down: Vec::new(), stick: [None, None] });
+            },
+            Err(err) => eprintln!("can't open controller: {}", err),
+        }
+    }
+
+    /* the player a controller belongs to, by its joystick instance id */
+    fn player(&self, instance_id: u32) -> Option<usize> {
+        self.pads.iter().position(|pad| {
+            pad.as_ref().is_some_and(|pad| pad.controller.instance_id() == instance_id)
+        })
+    }
+
+    fn button(&mut self, player: usize, button: Button, pressed: bool,
+              held: &mut HeldKeys, keypad: &mut Keypad) {
+        let Some(&key) = self.maps[player].buttons.get(&button) else {
+            return;
+        };
+        let Some(pad) = self.pads[player].as_mut() else {
+            return;
+        };
+
+        if pressed {
+            pad.down.push(key);
+            held.press(keypad, key);
+        } else if let Some(pos) = pad.down.iter().position(|&down| down == key) {
+            pad.down.swap_remove(pos);
+            held.release(keypad, key);
+        }
+    }
+
+    /* the left stick works as a second D-pad */
+    fn stick(&mut self, player: usize, axis: Axis, value: i16,
+             held: &mut HeldKeys, keypad: &mut Keypad) {
+        let (index, negative, positive) = match axis {
+            Axis::LeftX => (0, Button::DPadLeft, Button::DPadRight),
+            Axis::LeftY => (1, Button::DPadUp, Button::DPadDown),
+            _ => return,
+        };
+
+        let direction = if value <= -STICK_DEADZONE {
+            Some(negative)
+        } else if value >= STICK_DEADZONE {
+            Some(positive)
+        } else {
+            None
+        };
+
+        let Some(pad) = self.pads[player].as_mut() else {
+            return;
+        };
+        let previous = pad.stick[index];
+        if previous == direction {
+            return;
+        }
+        pad.stick[index] = direction;
+
+        if let Some(button) = previous {
+            self.button(player, button, false, held, keypad);
+        }
+        if let Some(button) = direction {
+            self.button(player, button, true, held, keypad);
+        }
+    }
+}
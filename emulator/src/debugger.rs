@@ -0,0 +1,146 @@
+use std::io;
+use std::io::Write;
+
+use chip8_core::Cpu;
+use chip8_core::CpuError;
+use chip8_core::Instruction;
+
+
+const HELP: &str = "\
+commands:
+    r               show registers
+    s [n]           step n instructions, default 1
+    n               skip the instruction at pc
+    m <addr> [len]  dump memory, addresses in hex
+    c               continue running, skipping the instruction that
+                    failed if pc is still on it
+    q               quit the emulator";
+
+/* a minimal debugger on stdin, entered when the program hits an error under
+ * --on-error debug. returns false if the user wants to quit */
+pub fn run(cpu: &mut Cpu, err: &CpuError) -> bool {
+    println!("{}", err);
+    print_registers(cpu);
+
+    let stdin = io::stdin();
+    let mut line = String::new();
+    /* the instruction that last failed, so continuing doesn't run straight
+     * back into it */
+    let mut fault = err.pc();
+
+    loop {
+        print!("debug> ");
+        let _ = io::stdout().flush();
+
+        line.clear();
+        match stdin.read_line(&mut line) {
+            Ok(0) | Err(_) => return false,
+            Ok(_) => {},
+        }
+
+        let mut words = line.split_whitespace();
+        let Some(command) = words.next() else {
+            continue;
+        };
+
+        match command {
+            "r" => print_registers(cpu),
+            "s" => {
+                let count = words.next().and_then(|n| n.parse().ok()).unwrap_or(1);
+                if let Some(err) = step(cpu, count) {
+                    fault = err.pc();
+                }
+            },
+            "n" => {
+                cpu.skip_instruction();
+                print_registers(cpu);
+            },
+            "m" => {
+                match words.next().and_then(hex) {
+                    Some(addr) => {
+                        let len = words.next().and_then(hex).unwrap_or(0x40);
+                        dump(cpu.memory(), addr, len);
+                    },
+                    None => println!("m needs an address"),
+                }
+            },
+            "c" => {
+                if cpu.pc() == fault {
+                    println!("skipping {:04X}", fault);
+                    cpu.skip_instruction();
+                }
+                return true;
+            },
+            "q" => return false,
+            "h" | "?" => println!("{}", HELP),
+            _ => println!("unknown command '{}'\n{}", command, HELP),
+        }
+    }
+}
+
+/* run count instructions, stopping at the first error, which is returned */
+fn step(cpu: &mut Cpu, count: usize) -> Option<CpuError> {
+    let mut error = None;
+
+    for _ in 0..count {
+        if let Err(err) = cpu.emulate_cycle() {
+            println!("{}", err);
+            error = Some(err);
+            break;
+        }
+    }
+
+    print_registers(cpu);
+    error
+}
+
+fn print_registers(cpu: &Cpu) {
+    let pc = cpu.pc();
+    let memory = cpu.memory();
+
+    /* the instruction about to run, if pc is still inside memory */
+    match memory.get(pc..pc + 2) {
+        Some(bytes) => {
+            let opcode = (bytes[0] as u16) << 8 | bytes[1] as u16;
+            match Instruction::decode(opcode) {
+                Some(instr) => println!("PC {:04X}  {:04X}  {:?}", pc, opcode, instr),
+                None => println!("PC {:04X}  {:04X}", pc, opcode),
+            }
+        },
+        None => println!("PC {:04X}  (outside memory)", pc),
+    }
+
+    for (reg, value) in cpu.registers().iter().enumerate() {
+        print!("V{:X} {:02X}  ", reg, value);
+        if reg % 8 == 7 {
+            println!();
+        }
+    }
+
+    println!("I {:04X}  DT {:02X}  ST {:02X}", cpu.i(), cpu.delay_timer(), cpu.sound_timer());
+
+    let stack: Vec<String> = cpu.stack().iter().map(|addr| format!("{:04X}", addr)).collect();
+    println!("stack [{}]", stack.join(" "));
+}
+
+/* hex dump len bytes of memory from addr, 16 to a line */
+fn dump(memory: &[u8], addr: usize, len: usize) {
+    let end = addr.saturating_add(len).min(memory.len());
+    if addr >= end {
+        println!("{:04X} is outside memory", addr);
+        return;
+    }
+
+    for (line, bytes) in memory[addr..end].chunks(16).enumerate() {
+        let hex: Vec<String> = bytes.iter().map(|byte| format!("{:02X}", byte)).collect();
+        println!("{:04X}  {}", addr.saturating_add(line * 16), hex.join(" "));
+    }
+}
+
+/* a hex number, with or without a leading $ or 0x */
+fn hex(s: &str) -> Option<usize> {
+    let digits = s.strip_prefix('$')
+                  .or_else(|| s.strip_prefix("0x"))
+                  .unwrap_or(s);
+    usize::from_str_radix(digits, 16).ok()
+}
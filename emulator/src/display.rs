@@ -1,65 +1,414 @@
-use sdl2;
+use std::fmt;
+use std::str::FromStr;
+
 use sdl2::pixels;
-use sdl2::rect::Rect;
 use sdl2::render::Canvas;
+use sdl2::render::TextureCreator;
+use sdl2::video::FullscreenType;
 use sdl2::video::Window;
+use sdl2::video::WindowContext;
+
+
+use chip8_core::consts::WIDTH;
+use chip8_core::consts::HEIGHT;
 
+use crate::config::Section;
+use crate::render::Screen;
 
-use crate::consts::WIDTH;
-use crate::consts::HEIGHT;
+/* colours for each combination of the two XO-CHIP bitplanes: off, plane 1,
+ * plane 2, both. the other machines only ever use the first two */
+pub type Palette = [pixels::Color; 4];
 
-const SCALE_FACTOR: u32 = 20;
-const SCREEN_WIDTH: u32 = (WIDTH as u32) * SCALE_FACTOR;
-const SCREEN_HEIGHT: u32 = (HEIGHT as u32) * SCALE_FACTOR;
+pub const DEFAULT_PALETTE: Palette = [pixels::Color::RGB(0, 0, 0),
+                                      pixels::Color::RGB(0, 255, 0),
+                                      pixels::Color::RGB(0, 128, 255),
+                                      pixels::Color::RGB(255, 255, 255)];
+
+/* built in palettes, by name. P cycles through them */
+pub const PRESETS: [(&str, Palette); 4] = [
+    ("green", DEFAULT_PALETTE),
+    /* an amber monochrome monitor */
+    ("amber", [pixels::Color::RGB(0x1a, 0x10, 0x00),
+               pixels::Color::RGB(0xff, 0xb0, 0x00),
+               pixels::Color::RGB(0x8a, 0x5c, 0x00),
+               pixels::Color::RGB(0xff, 0xe0, 0x90)]),
+    /* a greenish reflective LCD, dark pixels on a light background */
+    ("lcd", [pixels::Color::RGB(0x9b, 0xbc, 0x0f),
+             pixels::Color::RGB(0x0f, 0x38, 0x0f),
+             pixels::Color::RGB(0x30, 0x62, 0x30),
+             pixels::Color::RGB(0x8b, 0xac, 0x0f)]),
+    /* the VIP on a black and white TV */
+    ("vip-white", [pixels::Color::RGB(0x00, 0x00, 0x00),
+                   pixels::Color::RGB(0xff, 0xff, 0xff),
+                   pixels::Color::RGB(0x80, 0x80, 0x80),
+                   pixels::Color::RGB(0xc0, 0xc0, 0xc0)]),
+];
+
+/* frames a phosphor glow lasts when none are given */
+const PHOSPHOR_FRAMES: u32 = 8;
+
+/* sprites are erased and drawn again with XOR, so most games flicker. these
+ * hide it by keeping pixels on screen a little after the framebuffer turns
+ * them off. only what's shown changes, never the CPU */
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Persistence {
+    #[default]
+    Off,
+    /* a pixel that goes out fades to the background over this many frames,
+     * like the phosphor on a CRT */
+    Phosphor(u32),
+    /* a pixel is lit if it was in this frame or the one before */
+    Deflicker,
+}
+
+impl FromStr for Persistence {
+    type Err = String;
+
+    /* off, deflicker, or phosphor with an optional number of frames, like
+     * phosphor:12 */
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, frames) = match s.split_once(':') {
+            Some((name, frames)) => (name, Some(frames)),
+            None => (s, None),
+        };
+
+        match (name, frames) {
+            ("off", None) => Ok(Persistence::Off),
+            ("deflicker", None) => Ok(Persistence::Deflicker),
+            ("phosphor", None) => Ok(Persistence::Phosphor(PHOSPHOR_FRAMES)),
+            ("phosphor", Some(frames)) => frames.parse()
+                                            .ok()
+                                            .filter(|&frames| frames > 0)
+                                            .map(Persistence::Phosphor)
+                                            .ok_or_else(|| format!("'{}' is not a number of frames", frames)),
+            _ => Err(format!("unknown persistence '{}', expected off, phosphor[:frames] or deflicker", s)),
+        }
+    }
+}
+
+impl fmt::Display for Persistence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Persistence::Off => write!(f, "off"),
+            Persistence::Phosphor(frames) => write!(f, "phosphor:{}", frames),
+            Persistence::Deflicker => write!(f, "deflicker"),
+        }
+    }
+}
+
+/* how the window looks. set from the [display] config sections and the
+ * command line, and changed by hotkeys while running */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Options {
+    /* window pixels per pixel of the 64x32 display. larger display modes
+     * are scaled down to fit the same window */
+    pub scale: u32,
+    pub palette: Palette,
+    pub fullscreen: bool,
+    pub persistence: Persistence,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            scale: 20,
+            palette: DEFAULT_PALETTE,
+            fullscreen: false,
+            persistence: Persistence::Off,
+        }
+    }
+}
+
+impl Options {
+    /* apply a [display] config section: scale, palette (a preset or hex
+     * colours like --palette), foreground, background, fullscreen and
+     * persistence */
+    pub fn apply(&mut self, section: &Section) -> Result<(), String> {
+        for (name, value, line) in &section.entries {
+            let err = |msg: String| format!("line {}: {}", line, msg);
+
+            match name.as_str() {
+                "scale" => {
+                    self.scale = value.parse()
+                                    .ok()
+                                    .filter(|&scale| scale > 0)
+                                    .ok_or_else(|| err(format!("'{}' is not a scale", value)))?;
+                },
+                "palette" => self.palette = parse_palette(value).map_err(err)?,
+                "foreground" => self.palette[1] = parse_color(value).map_err(err)?,
+                "background" => self.palette[0] = parse_color(value).map_err(err)?,
+                "fullscreen" => {
+                    self.fullscreen = match value.as_str() {
+                        "yes" | "true" | "on" | "1" => true,
+                        "no" | "false" | "off" | "0" => false,
+                        _ => return Err(err(format!("fullscreen is yes or no, not '{}'", value))),
+                    };
+                },
+                "persistence" => self.persistence = value.parse().map_err(err)?,
+                _ => return Err(err(format!("unknown display option '{}'", name))),
+            }
+        }
+
+        Ok(())
+    }
+}
 
 
 pub struct Display {
-    canvas: Canvas<Window>
+    canvas: Canvas<Window>,
+    creator: TextureCreator<WindowContext>,
+    screen: Screen,
+    options: Options,
+    /* the last frame drawn, to draw again after the window or palette
+     * changes */
+    pixels: Vec<u8>,
+    width: usize,
+    height: usize,
+    /* something changed since the last present() */
+    dirty: bool,
+    afterglow: Afterglow,
+}
+
+/* what the persistence modes remember of earlier frames */
+#[derive(Default)]
+struct Afterglow {
+    /* the framebuffer as of the last two present()s */
+    current: Vec<u8>,
+    previous: Vec<u8>,
+    /* per pixel, the bitplanes it was last lit in and how brightly it
+     * still glows, 0-255 */
+    planes: Vec<u8>,
+    glow: Vec<u8>,
+}
+
+impl Afterglow {
+    /* move on to the next 60Hz frame */
+    fn tick(&mut self, pixels: &[u8], persistence: Persistence) {
+        if self.current.len() != pixels.len() {
+            /* the resolution changed, start over */
+            *self = Afterglow {
+                current: pixels.to_vec(),
+                previous: pixels.to_vec(),
+                planes: pixels.to_vec(),
+                glow: pixels.iter().map(|&p| if p != 0 { 0xff } else { 0 }).collect(),
+            };
+            return;
+        }
+
+        self.previous.copy_from_slice(&self.current);
+        self.current.copy_from_slice(pixels);
+
+        let fade = match persistence {
+            Persistence::Phosphor(frames) => (0xff / frames).max(1) as u8,
+            _ => 0xff,
+        };
+
+        for ((&pixel, planes), glow) in pixels.iter().zip(&mut self.planes).zip(&mut self.glow) {
+            if pixel != 0 {
+                *planes = pixel;
+                *glow = 0xff;
+            } else {
+                *glow = glow.saturating_sub(fade);
+            }
+        }
+    }
+
+    /* the colour to show pixel index in */
+    fn color(&self, index: usize, palette: &Palette, persistence: Persistence) -> pixels::Color {
+        match persistence {
+            Persistence::Off => palette[(self.current[index] & 0x03) as usize],
+            Persistence::Deflicker => {
+                palette[((self.current[index] | self.previous[index]) & 0x03) as usize]
+            },
+            Persistence::Phosphor(_) => {
+                let (off, on) = (palette[0], palette[(self.planes[index] & 0x03) as usize]);
+                let glow = self.glow[index] as u32;
+                let mix = |off: u8, on: u8| ((off as u32 * (0xff - glow) + on as u32 * glow) / 0xff) as u8;
+                pixels::Color::RGB(mix(off.r, on.r), mix(off.g, on.g), mix(off.b, on.b))
+            },
+        }
+    }
 }
 
 
 impl Display {
-    pub fn new(sdl_ctx: &sdl2::Sdl) -> Self {
+    pub fn new(sdl_ctx: &sdl2::Sdl, title: &str, options: Options) -> Self {
         let video = sdl_ctx.video().unwrap();
         let window = video
-                        .window("window", SCREEN_WIDTH, SCREEN_HEIGHT)
+                        .window(title, WIDTH as u32 * options.scale, HEIGHT as u32 * options.scale)
                         .position_centered()
+                        .resizable()
                         .opengl()
                         .build()
                         .unwrap();
 
-        let mut canvas = window.into_canvas().build().unwrap();
+        /* no vsync: the main loop's 60Hz clock is the only thing pacing
+         * emulation, whatever the monitor's refresh rate */
+        let canvas = window.into_canvas().build().unwrap();
+        let creator = canvas.texture_creator();
 
-        canvas.set_draw_color(pixels::Color::RGB(0,0,0));
-        canvas.clear();
-        canvas.present();
+        let mut display = Display {
+            canvas,
+            creator,
+            screen: Screen::new(),
+            options: Options { fullscreen: false, ..options },
+            pixels: vec![0; WIDTH * HEIGHT],
+            width: WIDTH,
+            height: HEIGHT,
+            dirty: true,
+            afterglow: Afterglow::default(),
+        };
 
-        Display { canvas: canvas }
+        if options.fullscreen {
+            display.toggle_fullscreen();
+        }
+        display.present();
+        display
     }
 
-    pub fn draw_screen(&mut self, pixels: &[[u8; WIDTH]; HEIGHT]) {
-        for (y, row) in pixels.iter().enumerate() {
-            for (x, &col) in row.iter().enumerate() {
-                let x = (x as u32) * SCALE_FACTOR;
-                let y = (y as u32) * SCALE_FACTOR;
+    /* take a new frame. it's shown by the next present(), so a program
+     * drawing many times a frame only costs one upload */
+    pub fn draw_screen(&mut self, pixels: &[u8], width: usize, height: usize) {
+        self.pixels.clear();
+        self.pixels.extend_from_slice(pixels);
+        self.width = width;
+        self.height = height;
+        self.dirty = true;
+    }
 
-                self.canvas.set_draw_color(color(col));
-                let _ = self.canvas
-                        .fill_rect(Rect::new(x as i32,
-                                            y as i32,
-                                            SCALE_FACTOR,
-                                            SCALE_FACTOR));
-            }
+    /* show the last frame again on the next present(), after the window
+     * or palette changes */
+    pub fn redraw(&mut self) {
+        self.dirty = true;
+    }
+
+    /* put the frame on screen if anything changed, or every time with
+     * persistence on since what's shown changes as pixels fade. called once
+     * per 60Hz frame */
+    pub fn present(&mut self) {
+        let persistence = self.options.persistence;
+        self.afterglow.tick(&self.pixels, persistence);
+
+        if !self.dirty && persistence == Persistence::Off {
+            return;
         }
+
+        let (afterglow, palette) = (&self.afterglow, &self.options.palette);
+        let uploaded = match persistence {
+            Persistence::Off => {
+                self.screen.upload(&self.creator, &self.pixels, self.width, self.height, palette)
+            },
+            _ => {
+                self.screen.upload_with(&self.creator, self.width, self.height,
+                                        |index| afterglow.color(index, palette, persistence))
+            },
+        };
+
+        let result = uploaded.and_then(|()| self.screen.draw(&mut self.canvas));
+        if let Err(err) = result {
+            eprintln!("can't draw: {}", err);
+        }
+
         self.canvas.present();
+        self.dirty = false;
+    }
+
+    pub fn set_title(&mut self, title: &str) {
+        let _ = self.canvas.window_mut().set_title(title);
+    }
 
+    pub fn options(&self) -> &Options {
+        &self.options
+    }
+
+    /* resize the window for a new scale, unless it's fullscreen */
+    pub fn set_scale(&mut self, scale: u32) {
+        self.options.scale = scale.max(1);
+
+        if !self.options.fullscreen {
+            let (width, height) = (WIDTH as u32 * self.options.scale, HEIGHT as u32 * self.options.scale);
+            let _ = self.canvas.window_mut().set_size(width, height);
+        }
+        self.redraw();
+    }
+
+    pub fn set_palette(&mut self, palette: Palette) {
+        self.options.palette = palette;
+        self.redraw();
+    }
+
+    /* switch to the next of the PRESETS, or the first after a custom
+     * palette. returns its name */
+    pub fn next_palette(&mut self) -> &'static str {
+        let current = PRESETS.iter().position(|(_, palette)| *palette == self.options.palette);
+        let (name, palette) = PRESETS[current.map_or(0, |index| (index + 1) % PRESETS.len())];
+        self.set_palette(palette);
+        name
+    }
+
+    /* switch to the next persistence mode: off, phosphor, deflicker.
+     * returns the new one */
+    pub fn next_persistence(&mut self) -> Persistence {
+        self.options.persistence = match self.options.persistence {
+            Persistence::Off => Persistence::Phosphor(PHOSPHOR_FRAMES),
+            Persistence::Phosphor(_) => Persistence::Deflicker,
+            Persistence::Deflicker => Persistence::Off,
+        };
+        self.redraw();
+        self.options.persistence
+    }
+
+    /* fill the screen, at the desktop's resolution, or go back to the
+     * window */
+    pub fn toggle_fullscreen(&mut self) {
+        let fullscreen = !self.options.fullscreen;
+        let mode = if fullscreen { FullscreenType::Desktop } else { FullscreenType::Off };
+
+        match self.canvas.window_mut().set_fullscreen(mode) {
+            Ok(()) => self.options.fullscreen = fullscreen,
+            Err(err) => eprintln!("can't change fullscreen: {}", err),
+        }
+        self.redraw();
     }
 }
 
-fn color(value: u8) -> pixels::Color {
-    if value == 0 {
-        pixels::Color::RGB(0,0,0)
-    } else {
-        pixels::Color::RGB(0,255,0)
+/* parse a palette given by the name of one of the PRESETS, or as up to four
+ * comma separated hex colours, e.g. "000000,00ff00". missing entries keep
+ * the default. names ignore case and take spaces for dashes, so
+ * "VIP white" is vip-white */
+pub fn parse_palette(s: &str) -> Result<Palette, String> {
+    let name = s.trim().to_lowercase().replace(' ', "-");
+    if let Some((_, palette)) = PRESETS.iter().find(|(preset, _)| *preset == name) {
+        return Ok(*palette);
+    }
+
+    let mut palette = DEFAULT_PALETTE;
+    let colors: Vec<&str> = s.split(',').map(str::trim).collect();
+
+    if colors.len() > palette.len() {
+        return Err(format!("palette '{}' has more than 4 colours", s));
+    }
+
+    if colors.len() == 1 && parse_color(colors[0]).is_err() {
+        let names: Vec<&str> = PRESETS.iter().map(|(name, _)| *name).collect();
+        return Err(format!("unknown palette '{}', expected one of: {}, or hex colours",
+                           s, names.join(", ")));
+    }
+
+    for (entry, hex) in palette.iter_mut().zip(colors) {
+        *entry = parse_color(hex)?;
     }
+
+    Ok(palette)
+}
+
+/* an RRGGBB hex colour, with or without a leading # */
+pub fn parse_color(s: &str) -> Result<pixels::Color, String> {
+    let hex = s.strip_prefix('#').unwrap_or(s);
+    let rgb = u32::from_str_radix(hex, 16)
+                .ok()
+                .filter(|_| hex.len() == 6 && hex.chars().all(|c| c.is_ascii_hexdigit()))
+                .ok_or_else(|| format!("'{}' is not an RRGGBB colour", s))?;
+
+    Ok(pixels::Color::RGB((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8))
 }
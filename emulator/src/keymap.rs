@@ -0,0 +1,173 @@
+use std::collections::HashMap;
+
+use sdl2::keyboard::Keycode;
+
+use chip8_core::Keypad;
+
+use crate::config::Section;
+
+
+/* the usual QWERTY layout:
+ *
+ *   1 2 3 C        1 2 3 4
+ *   4 5 6 D   <-   Q W E R
+ *   7 8 9 E        A S D F
+ *   A 0 B F        Z X C V
+ */
+const QWERTY: [(Keycode, usize); 16] = [
+    (Keycode::Num1, 0x1), (Keycode::Num2, 0x2), (Keycode::Num3, 0x3), (Keycode::Num4, 0xc),
+    (Keycode::Q, 0x4), (Keycode::W, 0x5), (Keycode::E, 0x6), (Keycode::R, 0xd),
+    (Keycode::A, 0x7), (Keycode::S, 0x8), (Keycode::D, 0x9), (Keycode::F, 0xe),
+    (Keycode::Z, 0xa), (Keycode::X, 0x0), (Keycode::C, 0xb), (Keycode::V, 0xf),
+];
+
+/* the digits on the numeric keypad are themselves, so the 2/4/6/8 most
+ * games steer with are arrows. the letters go on the keys around them:
+ *
+ *   / * -        A B C
+ *   7 8 9 +      7 8 9 D
+ *   4 5 6        4 5 6
+ *   1 2 3 Enter  1 2 3 E
+ *   0   .        0   F
+ */
+const NUMPAD: [(Keycode, usize); 16] = [
+    (Keycode::Kp0, 0x0), (Keycode::Kp1, 0x1), (Keycode::Kp2, 0x2), (Keycode::Kp3, 0x3),
+    (Keycode::Kp4, 0x4), (Keycode::Kp5, 0x5), (Keycode::Kp6, 0x6), (Keycode::Kp7, 0x7),
+    (Keycode::Kp8, 0x8), (Keycode::Kp9, 0x9), (Keycode::KpDivide, 0xa), (Keycode::KpMultiply, 0xb),
+    (Keycode::KpMinus, 0xc), (Keycode::KpPlus, 0xd), (Keycode::KpEnter, 0xe), (Keycode::KpPeriod, 0xf),
+];
+
+pub const LAYOUTS: [&str; 2] = ["qwerty", "numpad"];
+
+/* what the emulator's own keys do */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Hotkey {
+    Quit,
+    /* held down */
+    Rewind,
+    /* load a save state, or save one with shift */
+    Slot(usize),
+    Fullscreen,
+    Smaller,
+    Bigger,
+    Palette,
+    Persistence,
+    Mute,
+}
+
+/* the keys the emulator handles itself before the keypad sees them, so
+ * they can't be bound to CHIP-8 keys */
+const HOTKEYS: [(Keycode, Hotkey); 18] = [
+    (Keycode::Escape, Hotkey::Quit),
+    (Keycode::Backspace, Hotkey::Rewind),
+    (Keycode::F1, Hotkey::Slot(1)), (Keycode::F2, Hotkey::Slot(2)),
+    (Keycode::F3, Hotkey::Slot(3)), (Keycode::F4, Hotkey::Slot(4)),
+    (Keycode::F5, Hotkey::Slot(5)), (Keycode::F6, Hotkey::Slot(6)),
+    (Keycode::F7, Hotkey::Slot(7)), (Keycode::F8, Hotkey::Slot(8)),
+    (Keycode::F9, Hotkey::Slot(9)), (Keycode::F10, Hotkey::Slot(10)),
+    (Keycode::F11, Hotkey::Fullscreen),
+    (Keycode::Minus, Hotkey::Smaller),
+    (Keycode::Equals, Hotkey::Bigger),
+    (Keycode::P, Hotkey::Palette),
+    (Keycode::O, Hotkey::Persistence),
+    (Keycode::M, Hotkey::Mute),
+];
+
+pub fn hotkey(key: Keycode) -> Option<Hotkey> {
+    HOTKEYS.iter().find(|&&(hotkey, _)| hotkey == key).map(|&(_, action)| action)
+}
+
+/* which CHIP-8 key each host key presses. any number of host keys can press
+ * the same CHIP-8 key */
+pub struct Keymap {
+    keys: HashMap<Keycode, usize>,
+}
+
+impl Keymap {
+    /* one of the built in LAYOUTS */
+    pub fn layout(name: &str) -> Result<Keymap, String> {
+        let keys = match name {
+            "qwerty" => QWERTY,
+            "numpad" => NUMPAD,
+            _ => return Err(format!("unknown key layout '{}', expected one of: {}",
+                                    name, LAYOUTS.join(", "))),
+        };
+
+        Ok(Keymap { keys: keys.into_iter().collect() })
+    }
+
+    pub fn key_index(&self, key: Keycode) -> Option<usize> {
+        self.keys.get(&key).copied()
+    }
+
+    /* apply a [keys] config section. "layout = name" starts over from a
+     * built in layout, "X = key, key..." replaces the host keys for the
+     * CHIP-8 key X with SDL key names */
+    pub fn apply(&mut self, section: &Section) -> Result<(), String> {
+        for (name, value, line) in &section.entries {
+            let err = |msg: String| format!("line {}: {}", line, msg);
+
+            if name == "layout" {
+                *self = Keymap::layout(value).map_err(err)?;
+                continue;
+            }
+
+            let index = chip8_key(name).ok_or_else(|| err(format!("'{}' is not a key 0-F", name)))?;
+
+            self.keys.retain(|_, &mut mapped| mapped != index);
+
+            for key_name in value.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+                let key = Keycode::from_name(key_name)
+                                .ok_or_else(|| err(format!("unknown key '{}'", key_name)))?;
+                if hotkey(key).is_some() {
+                    return Err(err(format!("'{}' is a hotkey and can't be bound", key_name)));
+                }
+                self.keys.insert(key, index);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/* a CHIP-8 key by its hex digit, 0-F */
+pub fn chip8_key(name: &str) -> Option<usize> {
+    match name.len() {
+        1 => usize::from_str_radix(name, 16).ok(),
+        _ => None,
+    }
+}
+
+/* how many host inputs are holding each CHIP-8 key down. with more than one
+ * bound to a key it only comes up once all of them are released */
+#[derive(Default)]
+pub struct HeldKeys {
+    counts: [u32; 16],
+}
+
+impl HeldKeys {
+    pub fn press(&mut self, keypad: &mut Keypad, index: usize) {
+        self.counts[index] += 1;
+        keypad.press(index);
+    }
+
+    pub fn release(&mut self, keypad: &mut Keypad, index: usize) {
+        self.counts[index] = self.counts[index].saturating_sub(1);
+
+        if self.counts[index] == 0 {
+            keypad.release(index);
+        }
+    }
+
+    /* put the keypad back in step with what's held, after loading a state
+     * saved with other keys down */
+    pub fn sync(&self, keypad: &mut Keypad) {
+        for (index, &count) in self.counts.iter().enumerate() {
+            if count > 0 {
+                keypad.press(index);
+            } else {
+                keypad.release(index);
+            }
+        }
+    }
+}
@@ -1,67 +1,337 @@
-use std::env;
+use std::fs;
+use std::path::Path;
+use std::process;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant, SystemTime};
 use std::error::Error;
 
-use sdl2;
 use sdl2::event::Event;
-use sdl2::keyboard::Keycode;
+use sdl2::event::WindowEvent;
+use sdl2::keyboard::Mod;
 
-mod cpu;
+use chip8_core::Cpu;
+use chip8_core::Movie;
+use chip8_core::RandomAlgorithm;
+use chip8_core::Rewind;
+use chip8_core::Rng;
+
+mod args;
+mod audio;
+mod config;
+mod controller;
+mod debugger;
 mod display;
-mod keypad;
-mod consts;
+mod keymap;
+mod render;
+mod savestate;
 
-use cpu::Cpu;
+use args::Args;
+use args::OnError;
+use audio::Audio;
+use config::Config;
+use controller::ControllerMap;
+use controller::Controllers;
+use controller::DEFAULT_LAYOUTS;
 use display::Display;
+use display::Options;
+use keymap::HeldKeys;
+use keymap::Hotkey;
+use keymap::Keymap;
 
 
 fn main() -> Result<(), Box<dyn Error>> {
-    let args: Vec<String> = env::args().collect();
+    let args = Args::parse().unwrap_or_else(|err| {
+        eprintln!("{}\n\n{}", err, args::USAGE);
+        process::exit(2);
+    });
 
-    /* emulation cycle duration */
-    let cycle_duration = Duration::from_millis(2);
+    /* everything runs in 60Hz frames, like the display and timers */
+    let frame_duration = Duration::from_secs(1) / 60;
 
     /* SDL2 context */
     let sdl_ctx = sdl2::init().unwrap();
 
-    /* display */
-    let mut dp = Display::new(&sdl_ctx);
+    /* audio. the emulator is still playable without it */
+    let mut audio = Audio::new(&sdl_ctx, args.tone)
+                        .map_err(|err| eprintln!("no audio: {}", err))
+                        .ok();
 
     /* CPU */
-    let mut cpu = Cpu::new();
-    cpu.load_rom(&args[1]);
+    let mut cpu = Cpu::new(args.platform);
+    if let Some(quirks) = args.quirks {
+        cpu.set_quirks(quirks);
+    }
+    if let Some(ipf) = args.instructions_per_frame {
+        cpu.set_instructions_per_frame(ipf);
+    }
+    cpu.load_rom(&args.rom).unwrap_or_else(|err| {
+        eprintln!("{}: {}", args.rom, err);
+        process::exit(2);
+    });
+
+    /* print the seed so a run can be repeated with --seed */
+    let seed = args.seed.unwrap_or_else(|| {
+        SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_or(0, |time| time.as_nanos() as u64)
+    });
+    println!("seed {}", seed);
+    cpu.set_rng(Rng::new(RandomAlgorithm::default(), seed));
+
+    /* keys, controller buttons and display options, from the config
+     * file's [keys], [controller1], [controller2] and [display] and any
+     * sections for this ROM */
+    let config = Config::load(args.config.as_deref()).unwrap_or_else(|err| {
+        eprintln!("config: {}", err);
+        process::exit(2);
+    });
+    let rom_name = Path::new(&args.rom).file_name().unwrap_or_default().to_string_lossy();
+    println!("{} hash {:016x}", rom_name, cpu.rom_hash());
+
+    /* display, from the [display] sections with the command line on top */
+    let mut options = Options::default();
+    for section in config.sections("display", &rom_name, cpu.rom_hash()) {
+        options.apply(section).unwrap_or_else(|err| {
+            eprintln!("config: [{}] {}", section.name, err);
+            process::exit(2);
+        });
+    }
+    args.display_options(&mut options);
+
+    let title = format!("{} - chip8-emu", rom_name);
+    let mut dp = Display::new(&sdl_ctx, &title, options);
+
+    let mut keymap = Keymap::layout("qwerty")?;
+    for section in config.sections("keys", &rom_name, cpu.rom_hash()) {
+        keymap.apply(section).unwrap_or_else(|err| {
+            eprintln!("config: [{}] {}", section.name, err);
+            process::exit(2);
+        });
+    }
+
+    let mut maps = [ControllerMap::layout(DEFAULT_LAYOUTS[0])?, ControllerMap::layout(DEFAULT_LAYOUTS[1])?];
+    for (player, map) in maps.iter_mut().enumerate() {
+        let name = format!("controller{}", player + 1);
+        for section in config.sections(&name, &rom_name, cpu.rom_hash()) {
+            map.apply(section).unwrap_or_else(|err| {
+                eprintln!("config: [{}] {}", section.name, err);
+                process::exit(2);
+            });
+        }
+    }
+
+    /* controllers are optional too */
+    let mut controllers = Controllers::new(&sdl_ctx, maps)
+                            .map_err(|err| eprintln!("no controllers: {}", err))
+                            .ok();
+    let mut held = HeldKeys::default();
 
     let mut event_pump = sdl_ctx.event_pump().unwrap();
+    let mut next_frame = Instant::now();
+    /* under --on-error halt the program stops but the window stays up */
+    let mut crashed = false;
+
+    /* a state every frame, played back while backspace is held */
+    let mut rewind = Rewind::new(args.rewind_seconds as usize * 60);
+    let mut rewinding = false;
+
+    /* a movie starts from the state it was recorded from. nothing may
+     * jump to another state while one is recording or playing */
+    let mut recording = args.record.as_ref().map(|_| Movie::record(&cpu, seed));
+    let mut playing = args.play.as_ref().map(|path| {
+        let movie = start_movie(path, &mut cpu).unwrap_or_else(|err| {
+            eprintln!("{}: {}", path, err);
+            process::exit(2);
+        });
+        println!("playing {} frames, seed {}", movie.frames.len(), movie.seed);
+        movie
+    });
+    let mut movie_frame = 0;
 
     'main: loop {
         for event in event_pump.poll_iter() {
+            if let Some(controllers) = controllers.as_mut() {
+                controllers.handle(&event, &mut held, &mut cpu.keypad);
+            }
+
             match event {
                 Event::Quit { .. } => { break 'main },
-                Event::KeyDown {keycode: Some(keycode), .. } => {
-                    if keycode == Keycode::Escape {
-                        break 'main;
-                    } else {
-                        cpu.keypad.press(keycode);
+                Event::KeyDown {keycode: Some(keycode), keymod, repeat: false, .. } => {
+                    match keymap::hotkey(keycode) {
+                        Some(Hotkey::Quit) => break 'main,
+                        Some(Hotkey::Rewind) => {
+                            if recording.is_some() || playing.is_some() {
+                                eprintln!("can't rewind during a movie");
+                            } else {
+                                rewinding = true;
+                            }
+                        },
+                        /* shift+F saves, F on its own loads */
+                        Some(Hotkey::Slot(slot)) if keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD) => {
+                            match savestate::save(&cpu, &args.rom, slot) {
+                                Ok(()) => println!("saved state {}", slot),
+                                Err(err) => eprintln!("can't save state: {}", err),
+                            }
+                        },
+                        Some(Hotkey::Slot(_)) if recording.is_some() || playing.is_some() => {
+                            eprintln!("can't load a state during a movie");
+                        },
+                        Some(Hotkey::Slot(slot)) => {
+                            match savestate::load(&mut cpu, &args.rom, slot) {
+                                Ok(()) => {
+                                    println!("loaded state {}", slot);
+                                    held.sync(&mut cpu.keypad);
+                                    if crashed {
+                                        crashed = false;
+                                        dp.set_title(&title);
+                                    }
+                                    let output = cpu.output();
+                                    dp.draw_screen(output.vram, output.width, output.height);
+                                },
+                                Err(err) => eprintln!("can't load state: {}", err),
+                            }
+                        },
+                        Some(Hotkey::Fullscreen) => dp.toggle_fullscreen(),
+                        Some(Hotkey::Smaller) => dp.set_scale(dp.options().scale.saturating_sub(1)),
+                        Some(Hotkey::Bigger) => dp.set_scale(dp.options().scale + 1),
+                        Some(Hotkey::Palette) => println!("palette {}", dp.next_palette()),
+                        Some(Hotkey::Persistence) => println!("persistence {}", dp.next_persistence()),
+                        Some(Hotkey::Mute) => {
+                            if let Some(audio) = audio.as_mut() {
+                                let muted = audio.toggle_mute();
+                                println!("sound {}", if muted { "muted" } else { "on" });
+                            }
+                        },
+                        None => {
+                            if let Some(key) = keymap.key_index(keycode) {
+                                held.press(&mut cpu.keypad, key);
+                            }
+                        },
+                    }
+                }
+                Event::Window { win_event: WindowEvent::SizeChanged(..) | WindowEvent::Exposed, .. } => {
+                    dp.redraw();
+                }
+                Event::KeyUp {keycode: Some(keycode), .. } => {
+                    if keymap::hotkey(keycode) == Some(Hotkey::Rewind) {
+                        rewinding = false;
+                        held.sync(&mut cpu.keypad);
+                    } else if let Some(key) = keymap.key_index(keycode) {
+                        held.release(&mut cpu.keypad, key);
                     }
                 }
                 _ => {}
             }
         }
 
-        let output = cpu.emulate_cycle();
-        
-        /* only update screen if the vram has actually been changed */
-        if output.vram_changed {
-            dp.draw_screen(output.vram);
+        if rewinding {
+            /* step back a frame at a time, even out of a crash, until the
+             * oldest state kept */
+            match rewind.pop(&mut cpu) {
+                Ok(true) => {
+                    let output = cpu.output();
+                    dp.draw_screen(output.vram, output.width, output.height);
+
+                    if crashed {
+                        crashed = false;
+                        dp.set_title(&title);
+                    }
+                },
+                Ok(false) => {},
+                Err(err) => eprintln!("can't rewind: {}", err),
+            }
+
+            if let Some(audio) = audio.as_mut() {
+                audio.update(None, 64, false);
+            }
+        } else if !crashed {
+            if let Some(movie) = playing.as_ref() {
+                if !movie.play_frame(&mut cpu, movie_frame) {
+                    println!("movie finished");
+                    held.sync(&mut cpu.keypad);
+                    playing = None;
+                }
+                movie_frame += 1;
+            }
+
+            if let Some(movie) = recording.as_mut() {
+                movie.record_frame(&cpu);
+            }
+
+            /* once an error is skipped or the debugger carries on, the rest
+             * of the frame runs and the timers tick as usual */
+            let mut debugged = false;
+            let result = cpu.run_frame_with(|cpu, err| match args.on_error {
+                OnError::Halt => Err(err),
+                OnError::Ignore => {
+                    eprintln!("{}, skipping", err);
+                    cpu.skip_instruction();
+                    Ok(())
+                },
+                OnError::Debug => {
+                    debugged = true;
+                    if debugger::run(cpu, &err) { Ok(()) } else { Err(err) }
+                },
+            }).map(|_| ());
+            rewind.push(&cpu);
+
+            if let Err(err) = result {
+                match args.on_error {
+                    OnError::Halt => {
+                        eprintln!("{}", err);
+                        dp.set_title(&format!("halted: {}", err));
+                        crashed = true;
+                    },
+                    /* only quitting the debugger ends a frame early */
+                    OnError::Ignore | OnError::Debug => break 'main,
+                }
+            }
+
+            let output = cpu.output();
+
+            /* only update screen if the vram has actually been changed. the
+             * debugger may have stepped through draws of its own */
+            if output.vram_changed || result.is_err() || debugged {
+                dp.draw_screen(output.vram, output.width, output.height);
+            }
+
+            if let Some(audio) = audio.as_mut() {
+                audio.update(output.pattern, output.pitch, output.beep && !crashed);
+            }
+
+            /* 00FD */
+            if output.halted {
+                break 'main;
+            }
         }
 
-        if output.beep {
-            println!("BEEP");
+        /* whatever the frame drew goes on screen at once */
+        dp.present();
+
+        /* sleep until the next frame is due. if we've fallen behind, don't
+         * try to catch up by running frames back to back */
+        next_frame += frame_duration;
+        let now = Instant::now();
+        if next_frame > now {
+            thread::sleep(next_frame - now);
+        } else {
+            next_frame = now;
         }
+    }
 
-        thread::sleep(cycle_duration);
+    if let (Some(movie), Some(path)) = (recording, args.record.as_ref()) {
+        match fs::write(path, movie.to_bytes()) {
+            Ok(()) => println!("recorded {} frames to {}", movie.frames.len(), path),
+            Err(err) => eprintln!("can't save movie: {}: {}", path, err),
+        }
     }
 
     Ok(())
 }
+
+/* load a movie and put the CPU in its start state */
+fn start_movie(path: &str, cpu: &mut Cpu) -> Result<Movie, Box<dyn Error>> {
+    let movie = Movie::from_bytes(&fs::read(path)?)?;
+    movie.start(cpu)?;
+    Ok(movie)
+}
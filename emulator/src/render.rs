@@ -0,0 +1,91 @@
+use sdl2::pixels::Color;
+use sdl2::pixels::PixelFormatEnum;
+use sdl2::rect::Rect;
+use sdl2::render::Canvas;
+use sdl2::render::RenderTarget;
+use sdl2::render::Texture;
+use sdl2::render::TextureCreator;
+
+
+/* the framebuffer as a single streaming texture. each frame is converted to
+ * RGB straight into the texture and drawn with one copy, rather than a
+ * fill_rect per pixel. this only needs a canvas, not a window, so the render
+ * benchmark runs it offscreen too */
+pub struct Screen {
+    /* sized for the display mode last uploaded, made again when that
+     * changes */
+    texture: Option<Texture>,
+    width: usize,
+    height: usize,
+}
+
+impl Screen {
+    pub fn new() -> Screen {
+        Screen { texture: None, width: 0, height: 0 }
+    }
+
+    /* convert a frame of bitplane values with the palette and upload it */
+    pub fn upload<T>(&mut self, creator: &TextureCreator<T>, pixels: &[u8],
+                     width: usize, height: usize, palette: &[Color; 4]) -> Result<(), String> {
+        self.upload_with(creator, width, height, |index| palette[(pixels[index] & 0x03) as usize])
+    }
+
+    /* upload a frame with the colour of each pixel, by its index in row
+     * major order, given by color */
+    pub fn upload_with<T>(&mut self, creator: &TextureCreator<T>, width: usize, height: usize,
+                          color: impl Fn(usize) -> Color) -> Result<(), String> {
+        if self.texture.is_none() || (width, height) != (self.width, self.height) {
+            if let Some(texture) = self.texture.take() {
+                /* safe as nothing else holds on to it */
+                unsafe { texture.destroy() };
+            }
+
+            let texture = creator
+                            .create_texture_streaming(PixelFormatEnum::RGB888, width as u32, height as u32)
+                            .map_err(|err| err.to_string())?;
+            self.texture = Some(texture);
+            self.width = width;
+            self.height = height;
+        }
+
+        let texture = self.texture.as_mut().unwrap();
+
+        texture.with_lock(None, |buffer, pitch| {
+            for (y, line) in buffer.chunks_mut(pitch).take(height).enumerate() {
+                for (x, out) in line.chunks_exact_mut(4).take(width).enumerate() {
+                    /* RGB888 is a native endian 0x00RRGGBB per pixel, the
+                     * format most windows already use, so drawing it needs
+                     * no conversion */
+                    let Color { r, g, b, .. } = color(y * width + x);
+                    out.copy_from_slice(&u32::from_be_bytes([0, r, g, b]).to_ne_bytes());
+                }
+            }
+        })
+    }
+
+    /* draw the last upload scaled by a whole number to fit the canvas and
+     * centred with black bars around it. a canvas smaller than the display
+     * mode, like a 64x32 window in hires, gets it shrunk to fit instead */
+    pub fn draw<T: RenderTarget>(&self, canvas: &mut Canvas<T>) -> Result<(), String> {
+        canvas.set_draw_color(Color::RGB(0, 0, 0));
+        canvas.clear();
+
+        let Some(texture) = &self.texture else {
+            return Ok(());
+        };
+
+        let (canvas_width, canvas_height) = canvas.output_size()?;
+        let (width, height) = (self.width as u32, self.height as u32);
+        let (fit_width, fit_height) = match (canvas_width / width).min(canvas_height / height) {
+            0 => {
+                let fit = (canvas_width as f32 / width as f32).min(canvas_height as f32 / height as f32);
+                (((width as f32 * fit) as u32).max(1), ((height as f32 * fit) as u32).max(1))
+            },
+            scale => (width * scale, height * scale),
+        };
+        let left = (canvas_width - fit_width) as i32 / 2;
+        let top = (canvas_height - fit_height) as i32 / 2;
+
+        canvas.copy(texture, None, Rect::new(left, top, fit_width, fit_height))
+    }
+}
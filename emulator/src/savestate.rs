@@ -0,0 +1,20 @@
+use std::fs;
+
+use chip8_core::Cpu;
+
+
+/* states are kept next to the ROM, e.g. roms/pong.ch8.1.state */
+fn path(rom: &str, slot: usize) -> String {
+    format!("{}.{}.state", rom, slot)
+}
+
+pub fn save(cpu: &Cpu, rom: &str, slot: usize) -> Result<(), String> {
+    let path = path(rom, slot);
+    fs::write(&path, cpu.save_state()).map_err(|err| format!("{}: {}", path, err))
+}
+
+pub fn load(cpu: &mut Cpu, rom: &str, slot: usize) -> Result<(), String> {
+    let path = path(rom, slot);
+    let data = fs::read(&path).map_err(|err| format!("{}: {}", path, err))?;
+    cpu.load_state(&data).map_err(|err| format!("{}: {}", path, err))
+}
@@ -0,0 +1,73 @@
+/* what the fuzz targets run, kept out of fuzz_targets/ so the regression
+ * test can replay the checked in inputs without libfuzzer */
+
+use chip8_core::Cpu;
+use chip8_core::Platform;
+use chip8_core::Quirks;
+use chip8_core::PLATFORMS;
+
+
+/* enough for pc to walk the NOPs empty memory decodes as all the way to the
+ * top of XO-CHIP's 64kB, few enough to keep the fuzzer fast */
+const MAX_CYCLES: usize = 40_000;
+
+/* a CPU input is
+ *
+ *   platform:u8 quirks:u8 count:u8 keypad:(u16 le * count) rom...
+ *
+ * the keypad entries are used a frame each, round and round. errors are
+ * skipped like the emulator's --on-error ignore so the fuzzer gets past
+ * them and into the rest of the ROM */
+pub fn cpu(data: &[u8]) {
+    let [platform, quirks, count, rest @ ..] = data else {
+        return;
+    };
+
+    let keys_len = (*count as usize * 2).min(rest.len());
+    let (keys, rom) = rest.split_at(keys_len);
+    let keys: Vec<u16> = keys.chunks_exact(2).map(|k| u16::from_le_bytes([k[0], k[1]])).collect();
+
+    let mut cpu = Cpu::new(platform_for(*platform));
+    cpu.set_quirks(Quirks::from_bits(*quirks));
+    cpu.load_rom_bytes(rom);
+
+    let mut cycles = 0;
+    let mut frame = 0;
+
+    while cycles < MAX_CYCLES {
+        if !keys.is_empty() {
+            cpu.keypad.set_bits(keys[frame % keys.len()]);
+        }
+
+        for _ in 0..cpu.instructions_per_frame() {
+            match cpu.emulate_cycle() {
+                Ok(output) if output.halted => return,
+                Ok(output) => assert_eq!(output.vram.len(), output.width * output.height),
+                Err(_) => cpu.skip_instruction(),
+            }
+            cycles += 1;
+        }
+
+        cpu.vblank();
+        frame += 1;
+    }
+
+    /* whatever state the program left, a save state has to load back */
+    let state = cpu.save_state();
+    cpu.load_state(&state).expect("save state didn't load");
+    assert_eq!(cpu.save_state(), state);
+}
+
+/* a disassembler input is platform:u8 followed by the ROM */
+pub fn disassembler(data: &[u8]) {
+    let [platform, rom @ ..] = data else {
+        return;
+    };
+
+    let listing = disassembler::disassemble(rom, &platform_for(*platform));
+    assert!(listing.lines().count() <= rom.len() / 2);
+}
+
+fn platform_for(byte: u8) -> Platform {
+    PLATFORMS[byte as usize % PLATFORMS.len()]
+}
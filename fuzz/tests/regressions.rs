@@ -0,0 +1,42 @@
+/* replay the checked in inputs without libfuzzer: regressions/ holds inputs
+ * that crashed before a fix, so they stay fixed, and edge-cases/ hand written
+ * ones for corners worth keeping an eye on that never crashed */
+
+use std::fs;
+use std::path::PathBuf;
+
+
+fn replay(target: &str, run: fn(&[u8])) {
+    let root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let mut count = 0;
+
+    for kind in ["regressions", "edge-cases"] {
+        /* a target may have nothing of either kind */
+        let Ok(entries) = fs::read_dir(root.join(kind).join(target)) else {
+            continue;
+        };
+
+        for entry in entries {
+            let path = entry.unwrap().path();
+            if path.file_name().is_some_and(|name| name.to_string_lossy().starts_with('.')) {
+                continue;
+            }
+
+            eprintln!("replaying {}", path.display());
+            run(&fs::read(&path).unwrap());
+            count += 1;
+        }
+    }
+
+    assert!(count > 0, "no inputs for {}", target);
+}
+
+#[test]
+fn cpu() {
+    replay("cpu", chip8_fuzz::cpu);
+}
+
+#[test]
+fn disassembler() {
+    replay("disassembler", chip8_fuzz::disassembler);
+}